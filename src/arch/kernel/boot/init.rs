@@ -4,7 +4,7 @@ use {
             kernel::{
                 interrupts,
                 descriptors::global, 
-                devices::timer,
+                devices::{serial, timer},
                 processor::enable_features,
             },
         }
@@ -19,6 +19,7 @@ pub fn initialize() {
     global::initialize();
     interrupts::initialize();
     timer::initialize();
+    serial::initialize();
 
     #[cfg(feature = "vga")]
     vga::initialize();
@@ -5,7 +5,7 @@ use {
             x86::*,
             kernel::{
                 interrupts::{
-                    handlers::{timer, unhandled_irq1, unhandled_irq2, page_fault},
+                    handlers::{timer, serial, unhandled_irq1, unhandled_irq2, page_fault},
                     exceptions::{alignment_check, bad_tss, coprocessor_segment_overrun, debug, double_fault, floating_point, general_protection, divide_by_zero, int_zero, int_three, invalid_opcode, machine_check, non_maskable, no_coprocessor, out_of_bound, reserved, segment_not_present, stack_fault, ExceptionStackFrame}
                 }
             },
@@ -137,6 +137,8 @@ impl InterruptDescriptorTable {
         for i in 33..40 {
             self.interrupts[i] = InterruptEntry::new(VAddr::from_usize(unhandled_irq1 as usize), KERNEL_CODE_SELECTOR, Ring::Ring0, SystemDescriptorTypes64::InterruptGate, 0);
         }
+        self.interrupts[36] = InterruptEntry::new(VAddr::from_usize(serial as usize), KERNEL_CODE_SELECTOR, Ring::Ring0, SystemDescriptorTypes64::InterruptGate, 0);
+
         for i in 40..INTERRUPT_ENTRIES {
             self.interrupts[i] = InterruptEntry::new(VAddr::from_usize(unhandled_irq2 as usize), KERNEL_CODE_SELECTOR, Ring::Ring0, SystemDescriptorTypes64::InterruptGate, 0);
         }
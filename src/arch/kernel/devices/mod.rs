@@ -1,3 +1,4 @@
+pub mod rtc;
 pub mod serial;
 pub mod timer;
 pub mod vga;
\ No newline at end of file
@@ -0,0 +1,117 @@
+use {
+    crate::arch::x86::{inb, outb},
+};
+
+const ADDRESS_PORT: u16 = 0x70;
+const DATA_PORT: u16 = 0x71;
+
+const SECONDS: u8 = 0x00;
+const MINUTES: u8 = 0x02;
+const HOURS: u8 = 0x04;
+const DAY: u8 = 0x07;
+const MONTH: u8 = 0x08;
+const YEAR: u8 = 0x09;
+const STATUS_A: u8 = 0x0A;
+const STATUS_B: u8 = 0x0B;
+
+/// Set while the clock updates its registers
+const UPDATE_IN_PROGRESS: u8 = 1 << 7;
+
+/// Registers hold binary values instead of BCD
+const BINARY_MODE: u8 = 1 << 2;
+
+/// Hours are counted from 0 to 23 instead of 1 to 12
+const HOUR_24_MODE: u8 = 1 << 1;
+
+/// Set in the hour register for afternoon hours in 12 hour mode
+const HOUR_PM: u8 = 1 << 7;
+
+fn read_register(register: u8) -> u8 {
+    unsafe {
+        outb(ADDRESS_PORT, register);
+        inb(DATA_PORT)
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+struct DateTime {
+    second: u8,
+    minute: u8,
+    hour: u8,
+    day: u8,
+    month: u8,
+    year: u8,
+}
+
+fn read_date_time() -> DateTime {
+    while read_register(STATUS_A) & UPDATE_IN_PROGRESS != 0 {}
+
+    DateTime {
+        second: read_register(SECONDS),
+        minute: read_register(MINUTES),
+        hour: read_register(HOURS),
+        day: read_register(DAY),
+        month: read_register(MONTH),
+        year: read_register(YEAR),
+    }
+}
+
+fn from_bcd(value: u8) -> u8 {
+    (value >> 4) * 10 + (value & 0x0F)
+}
+
+/// Returns the number of days between 1970-01-01 and the given date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+/// Reads the CMOS real time clock, which is expected to run in UTC, and
+/// returns the seconds since the Unix epoch.
+pub fn read_time() -> u64 {
+    // the registers are read until two reads agree, so that an update in
+    // between does not mix old and new values
+    let mut time = read_date_time();
+
+    loop {
+        let again = read_date_time();
+
+        if again == time {
+            break;
+        }
+
+        time = again;
+    }
+
+    let status = read_register(STATUS_B);
+    let pm = time.hour & HOUR_PM != 0;
+    let mut hour = time.hour & !HOUR_PM;
+
+    if status & BINARY_MODE == 0 {
+        time.second = from_bcd(time.second);
+        time.minute = from_bcd(time.minute);
+        hour = from_bcd(hour);
+        time.day = from_bcd(time.day);
+        time.month = from_bcd(time.month);
+        time.year = from_bcd(time.year);
+    }
+
+    if status & HOUR_24_MODE == 0 {
+        hour %= 12;
+
+        if pm {
+            hour += 12;
+        }
+    }
+
+    // the century register is not standardized, years are taken as 20xx
+    let days = days_from_civil(2000 + i64::from(time.year), i64::from(time.month), i64::from(time.day));
+    let seconds = days * 86400 + i64::from(hour) * 3600 + i64::from(time.minute) * 60 + i64::from(time.second);
+
+    seconds.max(0) as u64
+}
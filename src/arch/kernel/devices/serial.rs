@@ -2,10 +2,27 @@ use {
     crate::{
         format,
         sync::lock::WaitLockIrqSave,
-        arch::x86::outb,
+        scheduler::{
+            block_current_task, reschedule, wakeup_task,
+            task::PriorityTaskQueue,
+        },
+        arch::x86::{inb, outb},
     },
 };
 
+const INTERRUPT_ENABLE_REGISTER: u16 = 1;
+const MODEM_CONTROL_REGISTER: u16 = 4;
+const LINE_STATUS_REGISTER: u16 = 5;
+
+const RECEIVED_DATA_AVAILABLE: u8 = 1 << 0;
+const DATA_READY: u8 = 1 << 0;
+
+const DATA_TERMINAL_READY: u8 = 1 << 0;
+const REQUEST_TO_SEND: u8 = 1 << 1;
+const AUXILIARY_OUTPUT_2: u8 = 1 << 3;
+
+const RECEIVE_BUFFER_SIZE: usize = 4096;
+
 pub struct SerialPort {
     base: u16,
 }
@@ -15,6 +32,23 @@ impl SerialPort {
         Self { base }
     }
 
+    pub fn enable_receive_interrupt(&mut self) {
+        unsafe {
+            outb(self.base + INTERRUPT_ENABLE_REGISTER, RECEIVED_DATA_AVAILABLE);
+            outb(self.base + MODEM_CONTROL_REGISTER, DATA_TERMINAL_READY | REQUEST_TO_SEND | AUXILIARY_OUTPUT_2);
+        }
+    }
+
+    pub fn read_byte(&mut self) -> Option<u8> {
+        unsafe {
+            if inb(self.base + LINE_STATUS_REGISTER) & DATA_READY != 0 {
+                Some(inb(self.base))
+            } else {
+                None
+            }
+        }
+    }
+
     pub fn write_bytes(&mut self, buffer: &[u8]) {
         unsafe {
             for &b in buffer {
@@ -36,4 +70,102 @@ impl format::Write for SerialPort {
     }
 }
 
-pub static PORT: WaitLockIrqSave<SerialPort> = WaitLockIrqSave::new(SerialPort::new(0x3F8));
\ No newline at end of file
+pub static PORT: WaitLockIrqSave<SerialPort> = WaitLockIrqSave::new(SerialPort::new(0x3F8));
+
+/// Bytes received on COM1 that have not been consumed yet, together with
+/// the tasks waiting for them. Filled from the IRQ4 handler, so the buffer
+/// is a fixed ring and never allocates.
+pub struct ReceiveBuffer {
+    data: [u8; RECEIVE_BUFFER_SIZE],
+    head: usize,
+    length: usize,
+    waiting: PriorityTaskQueue,
+}
+
+unsafe impl Send for ReceiveBuffer {}
+
+impl ReceiveBuffer {
+    const fn new() -> Self {
+        Self {
+            data: [0; RECEIVE_BUFFER_SIZE],
+            head: 0,
+            length: 0,
+            waiting: PriorityTaskQueue::new(),
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        if self.length == RECEIVE_BUFFER_SIZE {
+            return;
+        }
+
+        self.data[(self.head + self.length) % RECEIVE_BUFFER_SIZE] = byte;
+        self.length += 1;
+    }
+
+    fn pop(&mut self) -> Option<u8> {
+        if self.length == 0 {
+            return None;
+        }
+
+        let byte = self.data[self.head];
+        self.head = (self.head + 1) % RECEIVE_BUFFER_SIZE;
+        self.length -= 1;
+
+        Some(byte)
+    }
+}
+
+static INPUT: WaitLockIrqSave<ReceiveBuffer> = WaitLockIrqSave::new(ReceiveBuffer::new());
+
+/// Drains the receive holding register into the input buffer and wakes up
+/// every task blocked in `read`. Called from the IRQ4 handler.
+pub fn receive() {
+    let mut input = INPUT.lock();
+
+    while let Some(byte) = PORT.lock().read_byte() {
+        input.push(byte);
+    }
+
+    while let Some(task) = input.waiting.pop() {
+        wakeup_task(task);
+    }
+}
+
+/// Copies received bytes into `buffer`, blocking the current task until at
+/// least one byte is available.
+pub fn read(buffer: &mut [u8]) -> usize {
+    if buffer.is_empty() {
+        return 0;
+    }
+
+    loop {
+        {
+            let mut input = INPUT.lock();
+
+            if input.length > 0 {
+                let mut count = 0;
+
+                while count < buffer.len() {
+                    match input.pop() {
+                        Some(byte) => buffer[count] = byte,
+                        None => break,
+                    }
+
+                    count += 1;
+                }
+
+                return count;
+            }
+
+            let task = block_current_task();
+            input.waiting.push(task);
+        }
+
+        reschedule();
+    }
+}
+
+pub fn initialize() {
+    PORT.lock().enable_receive_interrupt();
+}
@@ -11,6 +11,48 @@ use {
 
 const CLOCK_TICK_RATE: u32 = 1193182u32;
 
+/// Length of the interval the TSC is measured against in milliseconds
+const CALIBRATION_TIME: u32 = 50;
+
+/// Enables the gate of PIT channel 2 in the system control port
+const CHANNEL2_GATE: u8 = 1 << 0;
+
+/// Connects PIT channel 2 to the speaker
+const SPEAKER_ENABLE: u8 = 1 << 1;
+
+/// Output of PIT channel 2 in the system control port
+const CHANNEL2_OUTPUT: u8 = 1 << 5;
+
+/// Returns the current value of the time stamp counter.
+pub fn get_timestamp() -> u64 {
+    unsafe { rdtsc() }
+}
+
+/// Measures the frequency of the time stamp counter in Hz by counting its
+/// cycles while PIT channel 2 counts down a known interval.
+pub fn calibrate_tsc() -> u64 {
+    let latch = CLOCK_TICK_RATE / (1000 / CALIBRATION_TIME);
+
+    unsafe {
+        outb(0x61, (inb(0x61) & !SPEAKER_ENABLE) | CHANNEL2_GATE);
+
+        // channel 2, low and high byte, interrupt on terminal count
+        outb(0x43, 0xB0);
+        outb(0x42, (latch & 0xFF) as u8);
+        outb(0x42, (latch >> 8) as u8);
+
+        let start = rdtsc();
+
+        while inb(0x61) & CHANNEL2_OUTPUT == 0 {
+            memory_barrier();
+        }
+
+        let end = rdtsc();
+
+        (end - start) * 1000 / u64::from(CALIBRATION_TIME)
+    }
+}
+
 unsafe fn wait_some_time() {
     let start = rdtsc();
 
@@ -82,10 +82,11 @@ pub extern "x86-interrupt" fn invalid_opcode(stack_frame: ExceptionStackFrame) {
     abort();
 }
 
-pub extern "x86-interrupt" fn no_coprocessor(stack_frame: ExceptionStackFrame) {
-    info!("task `{}` receive a `Coprocessor Not Available` exception: `{:#?}`.", get_current_taskid(), stack_frame);
+/// Raised by the first use of the FPU after a task switch, which loads the
+/// FPU registers of the current task.
+pub extern "x86-interrupt" fn no_coprocessor(_stack_frame: ExceptionStackFrame) {
+    restore_fpu_state();
     end_of_interrupt(MASTER);
-    abort();
 }
 
 pub extern "x86-interrupt" fn double_fault(stack_frame: ExceptionStackFrame, error_code: u64) {
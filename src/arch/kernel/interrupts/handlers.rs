@@ -1,4 +1,3 @@
-use core::intrinsics::write_bytes;
 use x86::controlregs;
 use x86::irq::PageFaultError;
 use {
@@ -6,6 +5,7 @@ use {
         scheduler::*,
         arch::{
             kernel::{
+                devices::serial,
                 interrupts::{
                     end_of_interrupt, MASTER, SLAVE,
                     exceptions::ExceptionStackFrame,
@@ -14,10 +14,9 @@ use {
         },
     },
 };
-use crate::arch::memory::{physical, VirtualAddress};
-use crate::arch::memory::paging::{map, BasePageSize, PageSize, PageTableEntryFlags};
-use crate::consts::USER_ENTRY;
+use crate::arch::memory::{user, VirtualAddress};
 use crate::scheduler;
+use crate::time;
 
 pub extern "x86-interrupt" fn unhandled_irq1(stack_frame: ExceptionStackFrame, irq: u64) {
     info!("task {} receive a unhandled IRQ: {} {:#?}.", get_current_taskid(), irq, stack_frame);
@@ -37,38 +36,42 @@ pub extern "x86-interrupt" fn timer(stack_frame: ExceptionStackFrame) {
 		stack_frame
 	);
 
+    let now = time::tick();
+
     end_of_interrupt(MASTER);
-    schedule();
+    expire_timers(now);
+    timer_tick();
 }
 
-pub extern "x86-interrupt" fn page_fault(stack_frame: ExceptionStackFrame, error_code: u64) {
-    let mut virtual_address = unsafe { VirtualAddress::from_usize(controlregs::cr2()) };
-
-    if virtual_address > USER_ENTRY + 0x400000u64 - 64u64 * 1024u64 {
-        virtual_address = align_down!(virtual_address, BasePageSize::SIZE);
-
-        let physical_address = physical::allocate_aligned(BasePageSize::SIZE, BasePageSize::SIZE);
+pub extern "x86-interrupt" fn serial(stack_frame: ExceptionStackFrame) {
+    debug!(
+		"task {} receive serial interrupt!\n{:#?}.",
+		get_current_taskid(),
+		stack_frame
+	);
 
-        debug!("map 0x{:x} into the user space at 0x{:x}.", physical_address, virtual_address);
+    serial::receive();
+    end_of_interrupt(MASTER);
+}
 
-        map::<BasePageSize>(
-            virtual_address,
-            physical_address,
-            1,
-            PageTableEntryFlags::WRITABLE | PageTableEntryFlags::USER_ACCESSIBLE | PageTableEntryFlags::EXECUTE_DISABLE,
-        );
+pub extern "x86-interrupt" fn page_fault(stack_frame: ExceptionStackFrame, error_code: u64) {
+    let virtual_address = unsafe { VirtualAddress::from_usize(controlregs::cr2()) };
 
+    if user::resolve_fault(virtual_address) {
         unsafe {
-            write_bytes(virtual_address.as_mut_ptr::<u8>(), 0x00, BasePageSize::SIZE);
             controlregs::cr2_write(0);
         }
 
         end_of_interrupt(MASTER);
     } else {
-        let pferror = PageFaultError::from_bits_truncate(error_code as u32);
+        if user::is_stack_overflow(virtual_address) {
+            error!("stack overflow in task {} at {:#X}.", get_current_taskid(), virtual_address);
+        } else {
+            let pferror = PageFaultError::from_bits_truncate(error_code as u32);
 
-        error!("page fault (#PF) Exception: {:#?}.", stack_frame);
-        error!("virtual_address = {:#X}, page fault error = {}.", virtual_address, pferror);
+            error!("page fault (#PF) Exception: {:#?}.", stack_frame);
+            error!("virtual_address = {:#X}, page fault error = {}.", virtual_address, pferror);
+        }
 
         unsafe {
             controlregs::cr2_write(0);
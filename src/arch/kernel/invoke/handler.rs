@@ -1,10 +1,22 @@
 use {
     crate::{
-        arch::naked_asm,
+        arch::{asm, naked_asm},
         invoke::INVOKE_TABLE,
     },
 };
 
+/// Returns the user stack pointer which `invoke_handler` saved on top of the
+/// kernel stack when the current invoke was entered. The user registers
+/// pushed by the handler start at this address.
+pub fn saved_user_stack_pointer() -> usize {
+    unsafe {
+        let top: usize;
+        asm!("rdgsbase {}", out(reg) top, options(preserves_flags, nomem, nostack));
+
+        *((top - 8) as *const usize)
+    }
+}
+
 #[naked]
 pub unsafe extern "C" fn invoke_handler() {
     naked_asm!(
@@ -20,6 +32,15 @@ pub unsafe extern "C" fn invoke_handler() {
         "push r10",     // 4th invoke argument (will be moved to rcx later)
         "push r11",     // User flags register (set by invoke instruction)
 
+        // Save the callee-saved registers as well, so that a forked child
+        // or a new thread starts with the values of its parent
+        "push rbx",
+        "push rbp",
+        "push r12",
+        "push r13",
+        "push r14",
+        "push r15",
+
         // === SWITCH TO KERNEL CONTEXT ===
         // Switch GS segment register from user to kernel
         // This allows access to kernel per-CPU data structures
@@ -59,6 +80,12 @@ pub unsafe extern "C" fn invoke_handler() {
 
         // === RESTORE USER REGISTERS ===
         // Restore all saved registers in reverse order
+        "pop r15",
+        "pop r14",
+        "pop r13",
+        "pop r12",
+        "pop rbp",
+        "pop rbx",
         "pop r11",             // User flags (will be restored by sysretq)
         "pop r10",             // 4th argument
         "pop r9",              // 6th argument
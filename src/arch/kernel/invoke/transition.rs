@@ -1,7 +1,9 @@
 use {
     crate::{
-        arch::naked_asm,
-        consts::USER_ENTRY,
+        arch::{
+            naked_asm,
+            kernel::scheduling::tls::{set_user_fs_base, set_user_gs_base},
+        },
     },
 };
 
@@ -36,11 +38,52 @@ unsafe extern "C" fn jump_to_user_land(ds: usize, stack: usize, cs: usize, entry
     )
 }
 
-pub unsafe fn to_user_mode(func: usize) -> ! {
+/// Entry point of a forked task. Leaves the kernel through the same path as
+/// `invoke_handler`, popping the user registers saved at `stack` and
+/// returning zero as result of the invoke.
+///
+/// # Safety
+///
+/// `stack` must point to registers saved by `invoke_handler`.
+#[naked]
+pub unsafe extern "C" fn return_to_user_land(stack: u64) -> ! {
+    naked_asm!(
+        "cli",
+        "mov rsp, rdi",
+        "swapgs",
+
+        "pop r15",
+        "pop r14",
+        "pop r13",
+        "pop r12",
+        "pop rbp",
+        "pop rbx",
+        "pop r11",
+        "pop r10",
+        "pop r9",
+        "pop r8",
+        "pop rdi",
+        "pop rsi",
+        "pop rdx",
+        "pop rcx",
+
+        "xor eax, eax",
+        "sysretq",
+
+        options(noreturn)
+    )
+}
+
+/// Starts a new program image at `func`. The thread pointers of the old
+/// image are cleared.
+pub unsafe fn to_user_mode(func: usize, stack: usize) -> ! {
+    set_user_fs_base(0);
+    set_user_gs_base(0);
+
     jump_to_user_land(
         0x23,  // User data segment selector (GDT entry 4, DPL=3)
-        USER_ENTRY.as_usize() + 0x400000usize,  // User stack pointer
+        stack, // User stack pointer
         0x2b,  // User code segment selector (GDT entry 5, DPL=3)
-        USER_ENTRY.as_usize() | func,           // User entry point
+        func,  // User entry point
     )
 }
\ No newline at end of file
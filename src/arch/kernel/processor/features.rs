@@ -22,6 +22,11 @@ const TRANSLATION_CACHE_EXTENSION: u64 = 1 << 15;
 static mut PHYSICAL_ADDRESS_BITS: u8 = 0;
 static mut LINEAR_ADDRESS_BITS: u8 = 0;
 static mut SUPPORTS_1GIB_PAGES: bool = false;
+static mut SUPPORTS_XSAVE: bool = false;
+static mut FPU_AREA_SIZE: usize = FXSAVE_AREA_SIZE;
+
+/// Size of the legacy area written by FXSAVE
+const FXSAVE_AREA_SIZE: usize = 512;
 
 pub fn supports_1gib_pages() -> bool {
     unsafe { SUPPORTS_1GIB_PAGES }
@@ -35,6 +40,15 @@ pub fn get_physical_address_bits() -> u8 {
     unsafe { PHYSICAL_ADDRESS_BITS }
 }
 
+pub fn supports_xsave() -> bool {
+    unsafe { SUPPORTS_XSAVE }
+}
+
+/// Returns the size of the area the FPU, SSE and AVX registers are saved in.
+pub fn get_fpu_area_size() -> usize {
+    unsafe { FPU_AREA_SIZE }
+}
+
 pub fn enable_features() {
     let cpuid = CpuId::new();
     let mut cr0 = unsafe { cr0() };
@@ -42,7 +56,7 @@ pub fn enable_features() {
     cr0 |= Cr0::CR0_ALIGNMENT_MASK;
     cr0 |= Cr0::CR0_NUMERIC_ERROR;
     cr0 |= Cr0::CR0_MONITOR_COPROCESSOR;
-    cr0 &= !(Cr0::CR0_CACHE_DISABLE | Cr0::CR0_NOT_WRITE_THROUGH);
+    cr0 &= !(Cr0::CR0_CACHE_DISABLE | Cr0::CR0_NOT_WRITE_THROUGH | Cr0::CR0_EMULATE_COPROCESSOR);
 
     unsafe { cr0_write(cr0) };
 
@@ -77,10 +91,51 @@ pub fn enable_features() {
         cr4 |= Cr4::CR4_ENABLE_MACHINE_CHECK;
     }
 
+    let has_fxsr = match cpuid.get_feature_info() {
+        Some(finfo) => finfo.has_fxsave_fxstor() && finfo.has_sse(),
+        None => false,
+    };
+
+    if has_fxsr {
+        cr4 |= Cr4::CR4_ENABLE_SSE | Cr4::CR4_UNMASKED_SSE;
+    } else {
+        panic!("Olea-Base requires the CPU feature FXSR");
+    }
+
+    let has_xsave = match cpuid.get_feature_info() {
+        Some(finfo) => finfo.has_xsave(),
+        None => false,
+    };
+
+    if has_xsave {
+        cr4 |= Cr4::CR4_ENABLE_OS_XSAVE;
+    }
+
     cr4 &= !(Cr4::CR4_ENABLE_PPMC | Cr4::CR4_TIME_STAMP_DISABLE);
 
     unsafe { cr4_write(cr4) };
 
+    if has_xsave {
+        let mut xcr0 = Xcr0::XCR0_FPU_MMX_STATE | Xcr0::XCR0_SSE_STATE;
+
+        if cpuid.get_extended_state_info().is_some_and(|state| state.xcr0_supports_avx_256()) {
+            xcr0 |= Xcr0::XCR0_AVX_STATE;
+        }
+
+        unsafe {
+            xcr0_write(xcr0);
+
+            // the size reported by CPUID depends on the components enabled in XCR0
+            SUPPORTS_XSAVE = true;
+            FPU_AREA_SIZE = cpuid
+                .get_extended_state_info()
+                .expect("CPUID Extended State Info is not available!")
+                .xsave_area_size_enabled_features() as usize;
+        }
+
+        info!("enable XSAVE for {:?} ({} bytes per task).", xcr0, get_fpu_area_size());
+    }
+
     let has_syscall = match cpuid.get_extended_processor_and_feature_identifiers() {
         Some(finfo) => finfo.has_syscall_sysret(),
         None => false,
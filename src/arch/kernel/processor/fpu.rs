@@ -0,0 +1,125 @@
+use {
+    crate::arch::{
+        asm,
+        kernel::processor::features::{get_fpu_area_size, supports_xsave},
+        x86::{cr0, cr0_write, Cr0},
+    },
+    alloc::alloc::{alloc_zeroed, dealloc, Layout},
+    core::ptr::{copy_nonoverlapping, null_mut},
+};
+
+/// Alignment XSAVE requires of its save area
+const AREA_ALIGNMENT: usize = 64;
+
+/// x87 control word set by FNINIT
+const DEFAULT_CONTROL_WORD: u16 = 0x037F;
+
+/// Offset of MXCSR in the legacy area
+const MXCSR_OFFSET: usize = 24;
+
+/// MXCSR after reset, all SIMD exceptions masked
+const DEFAULT_MXCSR: u32 = 0x1F80;
+
+/// Saved x87, SSE and AVX registers of a task. The save area is allocated
+/// when the task uses the FPU for the first time.
+pub struct FpuState {
+    area: *mut u8,
+}
+
+impl FpuState {
+    pub const fn new() -> Self {
+        FpuState { area: null_mut() }
+    }
+
+    fn layout() -> Layout {
+        Layout::from_size_align(get_fpu_area_size(), AREA_ALIGNMENT).unwrap()
+    }
+
+    /// Returns the save area, which initially holds the register values
+    /// after reset. A zeroed XSAVE header marks the other components as
+    /// being in their initial state.
+    fn area(&mut self) -> *mut u8 {
+        if self.area.is_null() {
+            unsafe {
+                self.area = alloc_zeroed(Self::layout());
+                assert!(!self.area.is_null(), "unable to allocate the FPU save area.");
+
+                (self.area as *mut u16).write(DEFAULT_CONTROL_WORD);
+                (self.area.add(MXCSR_OFFSET) as *mut u32).write(DEFAULT_MXCSR);
+            }
+        }
+
+        self.area
+    }
+
+    /// Stores the registers of the FPU in the save area.
+    pub fn save(&mut self) {
+        let area = self.area();
+
+        unsafe {
+            if supports_xsave() {
+                asm!("xsave64 [{}]", in(reg) area, in("eax") u32::MAX, in("edx") u32::MAX, options(nostack, preserves_flags));
+            } else {
+                asm!("fxsave64 [{}]", in(reg) area, options(nostack, preserves_flags));
+            }
+        }
+    }
+
+    /// Loads the registers of the FPU from the save area.
+    pub fn restore(&mut self) {
+        let area = self.area();
+
+        unsafe {
+            if supports_xsave() {
+                asm!("xrstor64 [{}]", in(reg) area, in("eax") u32::MAX, in("edx") u32::MAX, options(nostack, preserves_flags));
+            } else {
+                asm!("fxrstor64 [{}]", in(reg) area, options(nostack, preserves_flags));
+            }
+        }
+    }
+}
+
+impl Default for FpuState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for FpuState {
+    fn clone(&self) -> Self {
+        let mut state = FpuState::new();
+
+        if !self.area.is_null() {
+            unsafe {
+                copy_nonoverlapping(self.area, state.area(), get_fpu_area_size());
+            }
+        }
+
+        state
+    }
+}
+
+impl Drop for FpuState {
+    fn drop(&mut self) {
+        if !self.area.is_null() {
+            unsafe {
+                dealloc(self.area, Self::layout());
+            }
+        }
+    }
+}
+
+/// Allows the use of the FPU until the next task switch.
+pub fn clear_task_switched() {
+    unsafe {
+        asm!("clts", options(nomem, nostack, preserves_flags));
+    }
+}
+
+/// Makes the next use of the FPU raise a `Coprocessor Not Available`
+/// exception, which loads the registers of the current task.
+pub fn set_task_switched() {
+    unsafe {
+        cr0_write(cr0() | Cr0::CR0_TASK_SWITCHED);
+    }
+}
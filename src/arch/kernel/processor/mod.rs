@@ -1,4 +1,5 @@
 pub mod features;
+pub mod fpu;
 pub mod shutdown;
 pub mod utilities;
 
@@ -1,14 +1,20 @@
 use {
     crate::{
         consts::*,
-        arch::memory::VirtualAddress,
+        arch::{
+            kernel::{
+                invoke::transition::return_to_user_land,
+                scheduling::tls::{get_user_fs_base, get_user_gs_base},
+            },
+            memory::VirtualAddress,
+        },
         scheduler::{
             task::*,
             exit, get_current_taskid,
         },
     },
     core::{
-        mem::size_of,
+        mem::{size_of, transmute},
         ptr::write_bytes,
     },
 };
@@ -16,6 +22,8 @@ use {
 #[cfg(target_arch = "x86_64")]
 #[repr(C, packed)]
 struct State {
+    /// GS base swapped in by `swapgs`
+    kernel_gs: u64,
     gs: u64,
     fs: u64,
     r15: u64,
@@ -56,7 +64,7 @@ struct State {
 extern "C" fn leave_task() -> ! {
     debug!("finished task {}.", get_current_taskid());
 
-    exit();
+    exit(0);
 }
 
 impl TaskFrame for Task {
@@ -87,6 +95,32 @@ impl TaskFrame for Task {
         }
     }
 
+    /// Prepares a task which leaves the kernel like the invoke of its parent
+    /// would, restoring the registers saved at `user_stack_pointer`. The
+    /// task inherits the FS and user GS base of the current task.
+    #[cfg(target_arch = "x86_64")]
+    fn create_fork_frame(&mut self, user_stack_pointer: VirtualAddress) {
+        unsafe {
+            let entry: extern "C" fn() = transmute(return_to_user_land as unsafe extern "C" fn(u64) -> !);
+            self.create_stack_frame(entry);
+
+            let state: *mut State = self.last_stack_pointer.as_mut_ptr();
+            (*state).rdi = user_stack_pointer.as_u64();
+            (*state).fs = get_user_fs_base() as u64;
+            (*state).kernel_gs = get_user_gs_base() as u64;
+        }
+    }
+
+    /// Sets the FS base the task starts with, which must be called after
+    /// the stack frame has been created.
+    #[cfg(target_arch = "x86_64")]
+    fn set_thread_pointer(&mut self, thread_pointer: usize) {
+        unsafe {
+            let state: *mut State = self.last_stack_pointer.as_mut_ptr();
+            (*state).fs = thread_pointer as u64;
+        }
+    }
+
     #[cfg(target_arch = "x86")]
     fn prepare_initial_task_frame(&mut self, func: extern "C" fn()) {
         unsafe {
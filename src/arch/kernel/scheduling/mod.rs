@@ -1,2 +1,3 @@
 pub mod switch;
-pub mod frame;
\ No newline at end of file
+pub mod frame;
+pub mod tls;
\ No newline at end of file
@@ -72,12 +72,23 @@ pub unsafe extern "C" fn perform_context_switch(_old_stack: *mut VirtualAddress,
         "rdgsbase rdx",
         "push rax",
         "push rdx",
+        // the GS base swapgs switches to, user GS inside an invoke
+        "mov ecx, 0xC0000102",
+        "rdmsr",
+        "shl rdx, 32",
+        "or rax, rdx",
+        "push rax",
         "mov [rdi], rsp",
         "mov rsp, rsi",
         "mov rax, cr0",
         "or rax, 8",
         "mov cr0, rax",
         "call {set_stack}",
+        "pop rax",
+        "mov rdx, rax",
+        "shr rdx, 32",
+        "mov ecx, 0xC0000102",
+        "wrmsr",
         "pop r15",
         "wrgsbase r15",
         "pop r15",
@@ -0,0 +1,39 @@
+use {
+    crate::arch::{
+        asm,
+        x86::{rdmsr, wrmsr, IA32_KERNEL_GSBASE},
+    },
+};
+
+/// Returns the FS base of the current task, which user space uses as
+/// thread pointer.
+pub fn get_user_fs_base() -> usize {
+    let base: usize;
+
+    unsafe {
+        asm!("rdfsbase {}", out(reg) base, options(preserves_flags, nomem, nostack));
+    }
+
+    base
+}
+
+/// Sets the FS base of the current task. It is saved and restored by
+/// `perform_context_switch`.
+pub fn set_user_fs_base(base: usize) {
+    unsafe {
+        asm!("wrfsbase {}", in(reg) base, options(preserves_flags, nomem, nostack));
+    }
+}
+
+/// Returns the GS base of user space. Inside an invoke `swapgs` has
+/// parked it in the kernel GS base MSR.
+pub fn get_user_gs_base() -> usize {
+    unsafe { rdmsr(IA32_KERNEL_GSBASE) as usize }
+}
+
+/// Sets the GS base user space sees after the next `swapgs`.
+pub fn set_user_gs_base(base: usize) {
+    unsafe {
+        wrmsr(IA32_KERNEL_GSBASE, base as u64);
+    }
+}
@@ -1,6 +1,7 @@
 pub mod paging;
 pub mod physical;
 pub mod r#virtual;
+pub mod user;
 
 use {
     crate::{
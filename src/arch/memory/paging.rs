@@ -2,14 +2,13 @@ use {
     crate::{
         arch::kernel::processor::features::{get_physical_address_bits, supports_1gib_pages},
         consts::*,
-        scheduler,
     },
     core::{
         arch::asm,
         convert::TryInto,
         marker::PhantomData,
         mem::size_of,
-        ptr::write_bytes,
+        ptr::{copy_nonoverlapping, write_bytes},
     },
     x86::{
         controlregs,
@@ -43,6 +42,8 @@ bitflags! {
 		const DIRTY = 1 << 6;
 		const HUGE_PAGE = 1 << 7;
 		const GLOBAL = 1 << 8;
+		const COPY_ON_WRITE = 1 << 9;
+		const SHARED = 1 << 10;
 		#[cfg(target_arch = "x86_64")]
 		const EXECUTE_DISABLE = 1 << 63;
 	}
@@ -95,15 +96,33 @@ impl PageTableEntry {
 		(self.physical_address_and_flags.as_usize() & PageTableEntryFlags::PRESENT.bits()) != 0
 	}
 
-	#[allow(dead_code)]
 	fn is_huge(&self) -> bool {
 		(self.physical_address_and_flags.as_usize() & PageTableEntryFlags::HUGE_PAGE.bits()) != 0
 	}
 
-	fn is_user(&self) -> bool {
+	pub fn is_user(&self) -> bool {
 		(self.physical_address_and_flags.as_usize() & PageTableEntryFlags::USER_ACCESSIBLE.bits()) != 0
 	}
 
+	pub fn is_writable(&self) -> bool {
+		(self.physical_address_and_flags.as_usize() & PageTableEntryFlags::WRITABLE.bits()) != 0
+	}
+
+	pub fn is_copy_on_write(&self) -> bool {
+		(self.physical_address_and_flags.as_usize() & PageTableEntryFlags::COPY_ON_WRITE.bits()) != 0
+	}
+
+	pub fn is_shared(&self) -> bool {
+		(self.physical_address_and_flags.as_usize() & PageTableEntryFlags::SHARED.bits()) != 0
+	}
+
+	pub fn flags(&self) -> PageTableEntryFlags {
+		PageTableEntryFlags::from_bits_truncate(
+			self.physical_address_and_flags.as_usize()
+				& ((BasePageSize::SIZE - 1) | PageTableEntryFlags::EXECUTE_DISABLE.bits()),
+		)
+	}
+
 	fn set(&mut self, physical_address: PhysicalAddress, flags: PageTableEntryFlags) {
 		if flags.contains(PageTableEntryFlags::HUGE_PAGE) {
 			assert_eq!(physical_address % LargePageSize::SIZE, 0, "physical address is not on a `2 MB` page boundary (physical_address = `{:#X}`).", physical_address);
@@ -272,7 +291,9 @@ trait PageTableMethods {
 	fn get_page_table_entry<S: PageSize>(&mut self, page: Page<S>) -> Option<PageTableEntry>;
 	fn map_page_in_this_table<S: PageSize>(&mut self, page: Page<S>, physical_address: PhysicalAddress, flags: PageTableEntryFlags) -> bool;
 	fn map_page<S: PageSize>(&mut self, page: Page<S>, physical_address: PhysicalAddress, flags: PageTableEntryFlags) -> bool;
+	fn unmap_page<S: PageSize>(&mut self, page: Page<S>);
 	fn drop_user_space(&mut self);
+	fn copy_user_space(&mut self, copy: &mut Self);
 }
 
 impl<L: PageTableLevel> PageTableMethods for PageTable<L> {
@@ -305,14 +326,47 @@ impl<L: PageTableLevel> PageTableMethods for PageTable<L> {
 		self.map_page_in_this_table::<S>(page, physical_address, flags)
 	}
 
+	default fn unmap_page<S: PageSize>(&mut self, page: Page<S>) {
+		assert_eq!(L::LEVEL, S::MAP_LEVEL);
+		let index = page.table_index::<L>();
+
+		if self.entries[index].is_present() {
+			self.entries[index].physical_address_and_flags = PhysicalAddress::zero();
+			page.flush_from_tlb();
+		}
+	}
+
 	default fn drop_user_space(&mut self) {
 		let last = 1 << PAGE_MAP_BITS;
 
+		// pages of PROT_NONE mappings are present without being user accessible
 		for index in 0..last {
-			if self.entries[index].is_present() && self.entries[index].is_user() {
+			if self.entries[index].is_present() {
 				let physical_address = self.entries[index].address();
 				debug!("free page frame at 0x{:x}.", physical_address);
-				physical::deallocate(physical_address, BasePageSize::SIZE);
+				physical::release_frame(physical_address);
+				self.entries[index].physical_address_and_flags = PhysicalAddress::zero();
+			}
+		}
+	}
+
+	default fn copy_user_space(&mut self, copy: &mut Self) {
+		let last = 1 << PAGE_MAP_BITS;
+
+		for index in 0..last {
+			if self.entries[index].is_present() {
+				// shared pages stay writable so both processes see the same memory
+				if self.entries[index].is_writable() && !self.entries[index].is_shared() {
+					let mut flags = self.entries[index].flags();
+					flags.remove(PageTableEntryFlags::WRITABLE);
+					flags.insert(PageTableEntryFlags::COPY_ON_WRITE);
+
+					let physical_address = self.entries[index].address();
+					self.entries[index].set(physical_address, flags);
+				}
+
+				physical::reference_frame(self.entries[index].address());
+				copy.entries[index] = self.entries[index];
 			}
 		}
 	}
@@ -327,7 +381,7 @@ where
 		let index = page.table_index::<L>();
 
 		if self.entries[index].is_present() {
-			if L::LEVEL > S::MAP_LEVEL {
+			if L::LEVEL > S::MAP_LEVEL && !self.entries[index].is_huge() {
 				let subtable = self.subtable::<S>(page);
 				subtable.get_page_table_entry::<S>(page)
 			} else {
@@ -368,6 +422,23 @@ where
 		}
 	}
 
+	fn unmap_page<S: PageSize>(&mut self, page: Page<S>) {
+		assert!(L::LEVEL >= S::MAP_LEVEL);
+		let index = page.table_index::<L>();
+
+		if !self.entries[index].is_present() {
+			return;
+		}
+
+		if L::LEVEL > S::MAP_LEVEL && !self.entries[index].is_huge() {
+			let subtable = self.subtable::<S>(page);
+			subtable.unmap_page::<S>(page);
+		} else {
+			self.entries[index].physical_address_and_flags = PhysicalAddress::zero();
+			page.flush_from_tlb();
+		}
+	}
+
 	fn drop_user_space(&mut self) {
 		let last = 1 << PAGE_MAP_BITS;
 		let table_address = self as *const PageTable<L> as usize;
@@ -378,10 +449,38 @@ where
 					let subtable_address = (table_address << PAGE_MAP_BITS) | (index << PAGE_BITS);
 					let subtable = unsafe { &mut *(subtable_address as *mut PageTable<L::SubtableLevel>) };
 					subtable.drop_user_space();
+
+					let physical_address = self.entries[index].address();
+					debug!("free page table at 0x{:x}.", physical_address);
+					physical::deallocate(physical_address, BasePageSize::SIZE);
+					self.entries[index].physical_address_and_flags = PhysicalAddress::zero();
 				}
 			}
 		}
 	}
+
+	fn copy_user_space(&mut self, copy: &mut Self) {
+		let last = 1 << PAGE_MAP_BITS;
+		let table_address = self as *const PageTable<L> as usize;
+
+		for index in 0..last {
+			if self.entries[index].is_present() && self.entries[index].is_user() {
+				let subtable_address = (table_address << PAGE_MAP_BITS) | (index << PAGE_BITS);
+				let subtable = unsafe { &mut *(subtable_address as *mut PageTable<L::SubtableLevel>) };
+				let physical_address = physical::allocate_aligned(BasePageSize::SIZE, BasePageSize::SIZE);
+				let subtable_copy = map_temporary::<L::SubtableLevel>(physical_address);
+
+				unsafe {
+					write_bytes(subtable_copy as *mut PageTable<L::SubtableLevel> as *mut u8, 0x00, BasePageSize::SIZE);
+				}
+
+				subtable.copy_user_space(subtable_copy);
+				unmap_temporary(subtable_copy);
+
+				copy.entries[index].set(physical_address, self.entries[index].flags());
+			}
+		}
+	}
 }
 
 impl<L: PageTableLevelWithSubtables> PageTable<L>
@@ -422,6 +521,7 @@ where
 				let physical_address = self.entries[index].address();
 				debug!("free page table at 0x{:x}.", physical_address);
 				physical::deallocate(physical_address, BasePageSize::SIZE);
+				self.entries[index].physical_address_and_flags = PhysicalAddress::zero();
 			}
 		}
 	}
@@ -463,9 +563,11 @@ pub fn virtual_to_physical(virtual_address: VirtualAddress) -> PhysicalAddress {
 pub fn unmap<S: PageSize>(virtual_address: VirtualAddress, count: usize) {
 	debug!("unmapping virtual address {:#X} ({} pages).", virtual_address, count);
 
-	let range = get_page_range::<S>(virtual_address, count);
 	let root_pagetable = unsafe { &mut *PML4_ADDRESS };
-	root_pagetable.map_pages(range, PhysicalAddress::zero(), PageTableEntryFlags::BLANK);
+
+	for page in get_page_range::<S>(virtual_address, count) {
+		root_pagetable.unmap_page(page);
+	}
 }
 
 pub fn map<S: PageSize>(virtual_address: VirtualAddress, physical_address: PhysicalAddress, count: usize, flags: PageTableEntryFlags) {
@@ -498,9 +600,160 @@ pub fn map_usr_entry(func: extern "C" fn()) {
 	interrupt_nested_enable(irq);
 }
 
+/// Maps the page frame at `physical_address` writable into kernel virtual
+/// memory and returns its address.
+pub fn map_frame(physical_address: PhysicalAddress) -> VirtualAddress {
+	let virtual_address = r#virtual::allocate_aligned(BasePageSize::SIZE, BasePageSize::SIZE);
+
+	map::<BasePageSize>(
+		virtual_address,
+		physical_address,
+		1,
+		PageTableEntryFlags::WRITABLE | PageTableEntryFlags::EXECUTE_DISABLE,
+	);
+
+	virtual_address
+}
+
+/// Removes a mapping created by `map_frame`.
+pub fn unmap_frame(virtual_address: VirtualAddress) {
+	unmap::<BasePageSize>(virtual_address, 1);
+	r#virtual::deallocate(virtual_address, BasePageSize::SIZE);
+}
+
+/// Maps the page table at `physical_address` into kernel virtual memory, so
+/// that the tables of an address space other than the current one can be
+/// filled in.
+fn map_temporary<'a, L: PageTableLevel>(physical_address: PhysicalAddress) -> &'a mut PageTable<L> {
+	let virtual_address = map_frame(physical_address);
+
+	unsafe { &mut *virtual_address.as_mut_ptr::<PageTable<L>>() }
+}
+
+fn unmap_temporary<L: PageTableLevel>(table: &mut PageTable<L>) {
+	unmap_frame(VirtualAddress::from_usize(table as *mut PageTable<L> as usize));
+}
+
+/// Creates the root page table of a forked process. The user pages of the
+/// current process are shared with the child; writable ones become
+/// read-only copy-on-write pages in both address spaces.
+pub fn fork_usr_pgd() -> PhysicalAddress {
+	let irq = interrupt_nested_disable();
+	let physical_address = create_usr_pgd();
+	let root_pagetable = unsafe { &mut *PML4_ADDRESS };
+	let copy = map_temporary::<PML4>(physical_address);
+
+	// the recursive entry is not user accessible and stays untouched
+	root_pagetable.copy_user_space(copy);
+	unmap_temporary(copy);
+
+	unsafe {
+		controlregs::cr3_write(controlregs::cr3());
+	}
+
+	interrupt_nested_enable(irq);
+
+	physical_address
+}
+
+/// Replaces the copy-on-write page containing `virtual_address` by a
+/// private writable page. Returns `false` if the page is not copy-on-write.
+pub fn resolve_copy_on_write(virtual_address: VirtualAddress) -> bool {
+	let irq = interrupt_nested_disable();
+	let page = Page::<BasePageSize>::including_address(virtual_address);
+	let root_pagetable = unsafe { &mut *PML4_ADDRESS };
+
+	let entry = match root_pagetable.get_page_table_entry(page) {
+		Some(entry) if entry.is_copy_on_write() => entry,
+		_ => {
+			interrupt_nested_enable(irq);
+			return false;
+		}
+	};
+
+	let mut flags = entry.flags();
+	flags.remove(PageTableEntryFlags::COPY_ON_WRITE);
+	flags.insert(PageTableEntryFlags::WRITABLE);
+
+	if physical::frame_references(entry.address()) == 1 {
+		debug!("reuse copy-on-write page at 0x{:x}.", page.address());
+
+		map::<BasePageSize>(page.address(), entry.address(), 1, flags);
+	} else {
+		debug!("copy copy-on-write page at 0x{:x}.", page.address());
+
+		let physical_address = physical::allocate_aligned(BasePageSize::SIZE, BasePageSize::SIZE);
+		let copy = map_temporary::<PT>(physical_address);
+
+		unsafe {
+			copy_nonoverlapping(
+				page.address().as_ptr::<u8>(),
+				copy as *mut PageTable<PT> as *mut u8,
+				BasePageSize::SIZE,
+			);
+		}
+
+		unmap_temporary(copy);
+		map::<BasePageSize>(page.address(), physical_address, 1, flags);
+		physical::release_frame(entry.address());
+	}
+
+	interrupt_nested_enable(irq);
+
+	true
+}
+
+/// Applies `flags` to the present pages in `[start, end)`.
+///
+/// Writable pages whose frame is shared with another address space stay
+/// read-only and are copied on the first write, unless they belong to a
+/// shared mapping.
+pub fn protect_pages(start: VirtualAddress, end: VirtualAddress, flags: PageTableEntryFlags) {
+	let irq = interrupt_nested_disable();
+	let root_pagetable = unsafe { &mut *PML4_ADDRESS };
+
+	for page in Page::<BasePageSize>::range(Page::including_address(start), Page::including_address(end - 1u64)) {
+		if let Some(entry) = root_pagetable.get_page_table_entry(page) {
+			let mut page_flags = flags;
+
+			let needs_copy = entry.is_copy_on_write() || physical::frame_references(entry.address()) > 1;
+
+			if entry.is_shared() {
+				page_flags.insert(PageTableEntryFlags::SHARED);
+			} else if page_flags.contains(PageTableEntryFlags::WRITABLE) && needs_copy {
+				page_flags.remove(PageTableEntryFlags::WRITABLE);
+				page_flags.insert(PageTableEntryFlags::COPY_ON_WRITE);
+			}
+
+			map::<BasePageSize>(page.address(), entry.address(), 1, page_flags);
+		}
+	}
+
+	interrupt_nested_enable(irq);
+}
+
+/// Unmaps the present pages in `[start, end)` and releases their frames.
+pub fn unmap_pages(start: VirtualAddress, end: VirtualAddress) {
+	let irq = interrupt_nested_disable();
+	let root_pagetable = unsafe { &mut *PML4_ADDRESS };
+
+	for page in Page::<BasePageSize>::range(Page::including_address(start), Page::including_address(end - 1u64)) {
+		if let Some(entry) = root_pagetable.get_page_table_entry(page) {
+			unmap::<BasePageSize>(page.address(), 1);
+			physical::release_frame(entry.address());
+		}
+	}
+
+	interrupt_nested_enable(irq);
+}
+
 pub fn drop_user_space() {
 	let root_pagetable = unsafe { &mut *PML4_ADDRESS };
 	root_pagetable.drop_user_space();
+
+	unsafe {
+		controlregs::cr3_write(controlregs::cr3());
+	}
 }
 
 pub fn create_usr_pgd() -> PhysicalAddress {
@@ -527,7 +780,12 @@ pub fn create_usr_pgd() -> PhysicalAddress {
 		let recursive_pgt_idx = BOOT_INFO.unwrap().recursive_index();
 		let pml4 = user_page_table.as_mut_ptr::<u64>();
 		for i in 0..recursive_pgt_idx + 2 {
-			*pml4.offset(i.try_into().unwrap()) = *recursive_pgt.offset(i.try_into().unwrap());
+			let entry = *recursive_pgt.offset(i.try_into().unwrap());
+
+			// user mappings belong to the parent process and are not shared
+			if entry & PageTableEntryFlags::USER_ACCESSIBLE.bits() as u64 == 0 {
+				*pml4.offset(i.try_into().unwrap()) = entry;
+			}
 		}
 
 		let pml4 = (user_page_table.as_usize() + BasePageSize::SIZE - size_of::<usize>()) as *mut PageTableEntry;
@@ -536,8 +794,6 @@ pub fn create_usr_pgd() -> PhysicalAddress {
 		unmap::<BasePageSize>(user_page_table, 1);
 		r#virtual::deallocate(user_page_table, BasePageSize::SIZE);
 
-		scheduler::set_root_page_table(physical_address);
-
 		interrupt_nested_enable(irq);
 
 		physical_address
@@ -3,6 +3,7 @@ use {
         memory::freelist::{FreeList, FreeListEntry},
         scheduler::DisabledPreemption,
     },
+    alloc::collections::BTreeMap,
     core::ops::Deref,
 };
 use crate::arch::kernel::BOOT_INFO;
@@ -13,6 +14,11 @@ use crate::arch::memory::{
 
 static mut PHYSICAL_FREE_LIST: FreeList<PhysicalAddress> = FreeList::new();
 
+/// Reference counts of page frames mapped by more than one address space,
+/// e.g. shared copy-on-write after a fork. Frames without an entry have a
+/// single owner.
+static mut FRAME_REFERENCES: BTreeMap<PhysicalAddress, usize> = BTreeMap::new();
+
 pub fn initialize() {
 	unsafe {
 		let regions = BOOT_INFO.unwrap().memory_map.deref();
@@ -61,4 +67,41 @@ pub fn deallocate(physical_address: PhysicalAddress, size: usize) {
 	unsafe {
 		PHYSICAL_FREE_LIST.deallocate(physical_address, size);
 	}
-}
\ No newline at end of file
+}
+
+/// Adds a reference to the page frame at `physical_address`.
+pub fn reference_frame(physical_address: PhysicalAddress) {
+	let _preemption = DisabledPreemption::new();
+	unsafe {
+		*FRAME_REFERENCES.entry(physical_address).or_insert(1) += 1;
+	}
+}
+
+/// Returns the number of address spaces mapping the page frame at `physical_address`.
+pub fn frame_references(physical_address: PhysicalAddress) -> usize {
+	let _preemption = DisabledPreemption::new();
+	unsafe { FRAME_REFERENCES.get(&physical_address).copied().unwrap_or(1) }
+}
+
+/// Drops a reference to the page frame at `physical_address` and releases
+/// the frame once it is no longer referenced.
+pub fn release_frame(physical_address: PhysicalAddress) {
+	let _preemption = DisabledPreemption::new();
+	let unused = unsafe {
+		match FRAME_REFERENCES.get_mut(&physical_address) {
+			Some(count) if *count > 2 => {
+				*count -= 1;
+				false
+			}
+			Some(_) => {
+				FRAME_REFERENCES.remove(&physical_address);
+				false
+			}
+			None => true,
+		}
+	};
+
+	if unused {
+		deallocate(physical_address, BasePageSize::SIZE);
+	}
+}
@@ -0,0 +1,216 @@
+use {
+    crate::{
+        error::numbers,
+        scheduler::get_current_process,
+        arch::memory::{
+            physical,
+            r#virtual::TASK_VIRTUAL_MEMORY_END,
+            paging::{get_page_table_entry, map, map_frame, resolve_copy_on_write, unmap_frame, virtual_to_physical, BasePageSize, PageSize, PageTableEntryFlags},
+            PhysicalAddress, VirtualAddress,
+        },
+        file::vfs::Error,
+    },
+    core::{
+        mem::size_of,
+        ptr::{copy_nonoverlapping, write_bytes},
+    },
+};
+
+/// Resolves a user fault at `virtual_address`. Returns `false` if the fault
+/// cannot be resolved.
+///
+/// Copy-on-write pages are copied. An address in an accessible region of
+/// the current process gets a page with the permissions of the region,
+/// taken from the page cache for file mappings and zeroed otherwise.
+pub fn resolve_fault(virtual_address: VirtualAddress) -> bool {
+    if get_page_table_entry::<BasePageSize>(virtual_address).is_some() {
+        return resolve_copy_on_write(virtual_address);
+    }
+
+    if virtual_address >= TASK_VIRTUAL_MEMORY_END {
+        return false;
+    }
+
+    let region = match get_current_process().borrow().address_space.find_region(virtual_address) {
+        Some(region) if region.flags.contains(PageTableEntryFlags::USER_ACCESSIBLE) => region.clone(),
+        _ => return false,
+    };
+
+    let virtual_address = align_down!(virtual_address, BasePageSize::SIZE);
+    let flags = region.flags;
+
+    if let Some(mapping) = region.mapping {
+        let offset = mapping.offset + (virtual_address - region.start).as_usize();
+        return map_file_page(virtual_address, mapping.file.page(offset), mapping.shared, flags);
+    }
+
+    let physical_address = physical::allocate_aligned(BasePageSize::SIZE, BasePageSize::SIZE);
+
+    debug!("map 0x{:x} into the user space at 0x{:x}.", physical_address, virtual_address);
+
+    // the frame is zeroed through a kernel mapping, so that the tables of
+    // the user mapping are created with the permissions of the region
+    let frame = map_frame(physical_address);
+
+    unsafe {
+        write_bytes(frame.as_mut_ptr::<u8>(), 0x00, BasePageSize::SIZE);
+    }
+
+    unmap_frame(frame);
+
+    map::<BasePageSize>(virtual_address, physical_address, 1, flags);
+
+    true
+}
+
+/// Returns whether a fault at `virtual_address` hit the guard page below
+/// the stack of the current process.
+pub fn is_stack_overflow(virtual_address: VirtualAddress) -> bool {
+    get_current_process().borrow().address_space.is_stack_guard(virtual_address)
+}
+
+/// Maps the page cache frame `page` at `virtual_address`. Private writable
+/// mappings get the frame copy-on-write, so that writes stay in the process.
+fn map_file_page(virtual_address: VirtualAddress, page: Result<PhysicalAddress, Error>, shared: bool, mut flags: PageTableEntryFlags) -> bool {
+    let physical_address = match page {
+        Ok(physical_address) => physical_address,
+        Err(error) => {
+            debug!("unable to read file page for 0x{:x}: {:?}", virtual_address, error);
+            return false;
+        }
+    };
+
+    if shared {
+        flags.insert(PageTableEntryFlags::SHARED);
+    } else if flags.contains(PageTableEntryFlags::WRITABLE) {
+        flags.remove(PageTableEntryFlags::WRITABLE);
+        flags.insert(PageTableEntryFlags::COPY_ON_WRITE);
+    }
+
+    debug!("map file page 0x{:x} into the user space at 0x{:x}.", physical_address, virtual_address);
+
+    physical::reference_frame(physical_address);
+    map::<BasePageSize>(virtual_address, physical_address, 1, flags);
+
+    true
+}
+
+/// Checks that `[address, address + length)` lies in the user half of the
+/// address space.
+///
+/// Every page has to be mapped user accessible, and writable if `write` is
+/// set. Pages populated on demand are faulted in.
+pub fn access_ok(address: usize, length: usize, write: bool) -> Result<(), i32> {
+    if length == 0 {
+        return Ok(());
+    }
+
+    let end = address.checked_add(length).ok_or(numbers::BAD_ADDRESS)?;
+    if end > TASK_VIRTUAL_MEMORY_END.as_usize() {
+        return Err(numbers::BAD_ADDRESS);
+    }
+
+    let is_accessible = |page: VirtualAddress| {
+        matches!(
+            get_page_table_entry::<BasePageSize>(page),
+            Some(entry) if entry.is_user() && (!write || entry.is_writable())
+        )
+    };
+
+    let mut page = align_down!(address, BasePageSize::SIZE);
+    while page < end {
+        let virtual_address = VirtualAddress::from_usize(page);
+
+        let accessible = is_accessible(virtual_address)
+            || (resolve_fault(virtual_address) && is_accessible(virtual_address));
+
+        if !accessible {
+            return Err(numbers::BAD_ADDRESS);
+        }
+
+        page += BasePageSize::SIZE;
+    }
+
+    Ok(())
+}
+
+/// Returns the frame address backing the user address `address`. The page
+/// is faulted in writable, so that copy-on-write does not move it later.
+pub fn user_to_physical(address: usize) -> Result<PhysicalAddress, i32> {
+    access_ok(address, 1, true)?;
+
+    Ok(virtual_to_physical(VirtualAddress::from_usize(address)))
+}
+
+/// Copies `destination.len()` bytes from the user pointer `source`.
+// `source` is only read after `access_ok` found every page of the range
+// mapped readable for the user, so it cannot reach kernel memory
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn copy_from_user(destination: &mut [u8], source: *const u8) -> Result<(), i32> {
+    access_ok(source as usize, destination.len(), false)?;
+
+    unsafe {
+        copy_nonoverlapping(source, destination.as_mut_ptr(), destination.len());
+    }
+
+    Ok(())
+}
+
+/// Copies `source` to the user pointer `destination`.
+// `destination` is only written after `access_ok` found every page of the
+// range mapped writable for the user
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn copy_to_user(destination: *mut u8, source: &[u8]) -> Result<(), i32> {
+    access_ok(destination as usize, source.len(), true)?;
+
+    unsafe {
+        copy_nonoverlapping(source.as_ptr(), destination, source.len());
+    }
+
+    Ok(())
+}
+
+/// Copies a null-terminated string from the user pointer `source` into
+/// `destination` and returns its length without the null byte. A length
+/// equal to `destination.len()` means no terminator was found.
+pub fn strncpy_from_user(destination: &mut [u8], source: *const u8) -> Result<usize, i32> {
+    for (index, byte) in destination.iter_mut().enumerate() {
+        let address = (source as usize).checked_add(index).ok_or(numbers::BAD_ADDRESS)?;
+
+        if index == 0 || address % BasePageSize::SIZE == 0 {
+            access_ok(address, 1, false)?;
+        }
+
+        *byte = unsafe { *(address as *const u8) };
+
+        if *byte == 0 {
+            return Ok(index);
+        }
+    }
+
+    Ok(destination.len())
+}
+
+/// Reads a single value of type `T` from user memory.
+// `source` is only read after `access_ok` found the value below the end of
+// the user space and its pages mapped for the user
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn get_user<T: Copy>(source: *const T) -> Result<T, i32> {
+    access_ok(source as usize, size_of::<T>(), false)?;
+
+    unsafe { Ok(source.read_unaligned()) }
+}
+
+/// Writes a single value of type `T` to user memory.
+// `destination` is only written after `access_ok` found its pages mapped
+// writable for the user, with copy-on-write pages already copied
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn put_user<T>(destination: *mut T, value: T) -> Result<(), i32> {
+    access_ok(destination as usize, size_of::<T>(), true)?;
+
+    unsafe {
+        destination.write_unaligned(value);
+    }
+
+    Ok(())
+}
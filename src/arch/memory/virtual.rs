@@ -11,9 +11,12 @@ static mut KERNEL_FREE_LIST: FreeList<VirtualAddress> = FreeList::new();
 
 pub const KERNEL_VIRTUAL_MEMORY_START: VirtualAddress = VirtualAddress(0x8000_0000u64);
 
-pub const KERNEL_VIRTUAL_MEMORY_END: VirtualAddress = VirtualAddress(0x800_0000_0000u64);
+/// End of the kernel virtual memory, which stays in the first entry of the
+/// root page table. That entry is shared by all processes, user space starts
+/// behind it.
+pub const KERNEL_VIRTUAL_MEMORY_END: VirtualAddress = VirtualAddress(0x80_0000_0000u64);
 
-const TASK_VIRTUAL_MEMORY_END: VirtualAddress = VirtualAddress(0x8000_0000_0000u64);
+pub const TASK_VIRTUAL_MEMORY_END: VirtualAddress = VirtualAddress(0x8000_0000_0000u64);
 
 pub fn initialize() {
 	let entry = FreeListEntry {
@@ -1,12 +1,12 @@
 pub use {
     x86::{
         Ring,
-        io::outb,
+        io::{inb, outb},
         cpuid::CpuId,
         segmentation::*,
-        controlregs::{cr3_write, cr0, cr0_write, cr4, cr4_write, Cr0, Cr4},
+        controlregs::{cr3_write, cr0, cr0_write, cr4, cr4_write, xcr0_write, Cr0, Cr4, Xcr0},
         dtables::{lgdt, lidt, DescriptorTablePointer},
         bits64::{paging::VAddr, segmentation::*, task::*},
-        msr::{rdmsr, wrmsr, IA32_EFER, IA32_FMASK, IA32_GS_BASE, IA32_LSTAR, IA32_STAR},
+        msr::{rdmsr, wrmsr, IA32_EFER, IA32_FMASK, IA32_GS_BASE, IA32_KERNEL_GSBASE, IA32_LSTAR, IA32_STAR},
     }
 };
\ No newline at end of file
@@ -12,7 +12,11 @@ pub struct Console;
 impl Write for Console {
 	fn write_str(&mut self, s: &str) -> Result {
 		cfg_if::cfg_if! {
-			if #[cfg(feature = "vga")] {
+			if #[cfg(test)] {
+				// host-side tests have no serial port
+				std::print!("{}", s);
+				Ok(())
+			} else if #[cfg(feature = "vga")] {
 				vga::VGA_SCREEN.lock().write_str(s)
 			} else {
 				serial::PORT.lock().write_str(s)
@@ -17,6 +17,24 @@ pub const NO_PRIORITIES: usize = 32;
 
 pub const TIMER_FREQ: u32 = 100;
 
+pub const TIME_SLICE: u64 = 5;
+
 pub const USER_ENTRY: VirtualAddress = VirtualAddress(0x20000000000u64);
 
+pub const USER_STACK_TOP: VirtualAddress = VirtualAddress(0x30000000000u64);
+
+pub const USER_STACK_SIZE: usize = 0x10000;
+
+pub const USER_STACK_LIMIT: usize = 0x800000;
+
+pub const USER_STACK_MAX: usize = 0x10000000;
+
+pub const INTERPRETER_BASE: VirtualAddress = VirtualAddress(0x30000000000u64);
+
+pub const INTERPRETER_END: VirtualAddress = VirtualAddress(0x40000000000u64);
+
+pub const MAPPING_START: VirtualAddress = VirtualAddress(0x40000000000u64);
+
+pub const MAPPING_END: VirtualAddress = VirtualAddress(0x80000000000u64);
+
 pub const HEAP_SIZE: usize = 8 * 1024 * 1024;
@@ -1,3 +1,9 @@
+use crate::{
+	file::vfs,
+	io,
+	scheduler,
+};
+
 pub mod numbers {
 	/// Operation not permitted
 	pub const OPERATION_NOT_PERMITTED: i32 = 1;
@@ -456,4 +462,140 @@ pub mod numbers {
 
 	/// Operation not supported on filesystem
 	pub const FILESYSTEM_OPERATION_NOT_SUPPORTED: i32 = 151;
-}
\ No newline at end of file
+}
+
+/// Conversion of kernel error types into the Linux errno values defined in
+/// `numbers`, which is what invoke handlers report back to user space.
+pub trait Errno {
+	fn errno(&self) -> i32;
+
+	/// Value returned in `rax` by an invoke that failed with this error
+	fn to_return(&self) -> isize {
+		-(self.errno() as isize)
+	}
+}
+
+impl Errno for i32 {
+	fn errno(&self) -> i32 {
+		*self
+	}
+}
+
+impl Errno for vfs::Error {
+	fn errno(&self) -> i32 {
+		match self {
+			vfs::Error::NotImplemented => numbers::NOT_IMPLEMENTED,
+			vfs::Error::InvalidArgument => numbers::INVALID_ARGUMENT,
+			vfs::Error::InvalidFsPath => numbers::FILE_NOT_FOUND,
+			vfs::Error::BadFileDescriptor => numbers::BAD_FILE_DESCRIPTOR,
+			vfs::Error::FileNotFound => numbers::FILE_NOT_FOUND,
+			vfs::Error::DirectoryNotFound => numbers::FILE_NOT_FOUND,
+			vfs::Error::PermissionDenied => numbers::PERMISSION_DENIED,
+			vfs::Error::IoError => numbers::IO_ERROR,
+			vfs::Error::OutOfMemory => numbers::OUT_OF_MEMORY,
+			vfs::Error::AlreadyExists => numbers::FILE_EXISTS,
+			vfs::Error::NotADirectory => numbers::NOT_A_DIRECTORY,
+			vfs::Error::IsADirectory => numbers::IS_A_DIRECTORY,
+			vfs::Error::SymlinkLoop => numbers::TOO_MANY_SYMLINKS,
+			vfs::Error::IllegalSeek => numbers::ILLEGAL_SEEK,
+		}
+	}
+}
+
+impl Errno for scheduler::error::Error {
+	fn errno(&self) -> i32 {
+		match self {
+			scheduler::error::Error::BadPriority => numbers::INVALID_ARGUMENT,
+			scheduler::error::Error::ValueOverflow => numbers::TOO_MANY_OPEN_FILES,
+			scheduler::error::Error::BadFileDescriptor => numbers::BAD_FILE_DESCRIPTOR,
+			scheduler::error::Error::FileNotFound => numbers::FILE_NOT_FOUND,
+			scheduler::error::Error::NoChildProcess => numbers::NO_CHILD_PROCESSES,
+		}
+	}
+}
+
+impl Errno for io::Error {
+	fn errno(&self) -> i32 {
+		match self {
+			io::Error::InvalidArgument => numbers::INVALID_ARGUMENT,
+			io::Error::NoBufferSpace => numbers::NO_BUFFER_SPACE,
+			io::Error::ValueOverflow => numbers::VALUE_OVERFLOW,
+			io::Error::ArgumentListTooLong => numbers::ARGUMENT_LIST_TOO_LONG,
+			io::Error::InvalidExecutable => numbers::INVALID_EXECUTABLE,
+			io::Error::FsError(error) => error.errno(),
+		}
+	}
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn linux_numbers() {
+	assert_eq!(numbers::OPERATION_NOT_PERMITTED, 1);
+	assert_eq!(numbers::FILE_NOT_FOUND, 2);
+	assert_eq!(numbers::ARGUMENT_LIST_TOO_LONG, 7);
+	assert_eq!(numbers::INVALID_EXECUTABLE, 8);
+	assert_eq!(numbers::BAD_FILE_DESCRIPTOR, 9);
+	assert_eq!(numbers::NO_CHILD_PROCESSES, 10);
+	assert_eq!(numbers::TRY_AGAIN, 11);
+	assert_eq!(numbers::OUT_OF_MEMORY, 12);
+	assert_eq!(numbers::BAD_ADDRESS, 14);
+	assert_eq!(numbers::FILE_EXISTS, 17);
+	assert_eq!(numbers::NOT_A_DIRECTORY, 20);
+	assert_eq!(numbers::IS_A_DIRECTORY, 21);
+	assert_eq!(numbers::INVALID_ARGUMENT, 22);
+	assert_eq!(numbers::TOO_MANY_OPEN_FILES, 24);
+	assert_eq!(numbers::ILLEGAL_SEEK, 29);
+	assert_eq!(numbers::NOT_IMPLEMENTED, 38);
+	assert_eq!(numbers::TOO_MANY_SYMLINKS, 40);
+	assert_eq!(numbers::VALUE_OVERFLOW, 75);
+	assert_eq!(numbers::NO_BUFFER_SPACE, 105);
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn vfs_errors() {
+	assert_eq!(vfs::Error::FileNotFound.errno(), 2);
+	assert_eq!(vfs::Error::InvalidFsPath.errno(), 2);
+	assert_eq!(vfs::Error::DirectoryNotFound.errno(), 2);
+	assert_eq!(vfs::Error::IoError.errno(), 5);
+	assert_eq!(vfs::Error::BadFileDescriptor.errno(), 9);
+	assert_eq!(vfs::Error::OutOfMemory.errno(), 12);
+	assert_eq!(vfs::Error::PermissionDenied.errno(), 13);
+	assert_eq!(vfs::Error::AlreadyExists.errno(), 17);
+	assert_eq!(vfs::Error::NotADirectory.errno(), 20);
+	assert_eq!(vfs::Error::IsADirectory.errno(), 21);
+	assert_eq!(vfs::Error::InvalidArgument.errno(), 22);
+	assert_eq!(vfs::Error::IllegalSeek.errno(), 29);
+	assert_eq!(vfs::Error::NotImplemented.errno(), 38);
+	assert_eq!(vfs::Error::SymlinkLoop.errno(), 40);
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn scheduler_errors() {
+	assert_eq!(scheduler::error::Error::FileNotFound.errno(), 2);
+	assert_eq!(scheduler::error::Error::BadFileDescriptor.errno(), 9);
+	assert_eq!(scheduler::error::Error::NoChildProcess.errno(), 10);
+	assert_eq!(scheduler::error::Error::BadPriority.errno(), 22);
+	assert_eq!(scheduler::error::Error::ValueOverflow.errno(), 24);
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn io_errors() {
+	assert_eq!(io::Error::ArgumentListTooLong.errno(), 7);
+	assert_eq!(io::Error::InvalidExecutable.errno(), 8);
+	assert_eq!(io::Error::InvalidArgument.errno(), 22);
+	assert_eq!(io::Error::ValueOverflow.errno(), 75);
+	assert_eq!(io::Error::NoBufferSpace.errno(), 105);
+	assert_eq!(io::Error::FsError(vfs::Error::FileNotFound).errno(), 2);
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn negative_return() {
+	assert_eq!(numbers::BAD_ADDRESS.to_return(), -14);
+	assert_eq!(vfs::Error::FileNotFound.to_return(), -2);
+	assert_eq!(scheduler::error::Error::NoChildProcess.to_return(), -10);
+	assert_eq!(io::Error::InvalidExecutable.to_return(), -8);
+}
@@ -0,0 +1,111 @@
+use {
+	crate::{
+		arch::memory::{
+			paging::{map_frame, unmap_frame, BasePageSize, PageSize},
+			physical, PhysicalAddress,
+		},
+		sync::lock::WaitLockIrqSave,
+	},
+	alloc::{collections::BTreeMap, vec::Vec},
+	core::{cmp::{max, min}, ptr::write_bytes, slice},
+};
+
+/// Identifies the data of a file independent of the handles opened on it
+pub type NodeId = usize;
+
+/// Page frames holding file data, keyed by node and page aligned offset.
+/// The cache owns one reference to each frame, every mapping of a page
+/// adds another.
+static PAGE_CACHE: WaitLockIrqSave<BTreeMap<(NodeId, usize), PhysicalAddress>> = WaitLockIrqSave::new(BTreeMap::new());
+
+/// Runs `f` on the contents of the page frame at `physical_address`.
+fn with_frame<R>(physical_address: PhysicalAddress, f: impl FnOnce(&mut [u8]) -> R) -> R {
+	let virtual_address = map_frame(physical_address);
+	let page = unsafe { slice::from_raw_parts_mut(virtual_address.as_mut_ptr::<u8>(), BasePageSize::SIZE) };
+	let result = f(page);
+
+	unmap_frame(virtual_address);
+	result
+}
+
+/// Calls `f` for every cached page overlapping `length` bytes at `offset`
+/// with the file offset, frame, page offset and length of the overlap.
+fn for_each_cached(node: NodeId, offset: usize, length: usize, mut f: impl FnMut(usize, PhysicalAddress, usize, usize)) {
+	let cache = PAGE_CACHE.lock();
+	let end = offset + length;
+	let first = align_down!(offset, BasePageSize::SIZE);
+
+	for (&(_, page), &physical_address) in cache.range((node, first)..(node, end)) {
+		let start = max(page, offset);
+		let stop = min(page + BasePageSize::SIZE, end);
+
+		f(start, physical_address, start - page, stop - start);
+	}
+}
+
+/// Returns the frame caching the page at `offset` of `node`. On a miss a
+/// zeroed frame is filled by `fill` and added to the cache.
+pub fn get_page(node: NodeId, offset: usize, fill: impl FnOnce(&mut [u8])) -> PhysicalAddress {
+	let offset = align_down!(offset, BasePageSize::SIZE);
+	let mut cache = PAGE_CACHE.lock();
+
+	if let Some(physical_address) = cache.get(&(node, offset)) {
+		return *physical_address;
+	}
+
+	let physical_address = physical::allocate_aligned(BasePageSize::SIZE, BasePageSize::SIZE);
+
+	with_frame(physical_address, |page| {
+		unsafe {
+			write_bytes(page.as_mut_ptr(), 0x00, page.len());
+		}
+
+		fill(page);
+	});
+
+	debug!("cache page 0x{:x} of node 0x{:x} in frame 0x{:x}.", offset, node, physical_address);
+
+	cache.insert((node, offset), physical_address);
+	physical_address
+}
+
+/// Overwrites the parts of `buffer`, read at `offset` of `node`, which are
+/// held by cached pages, since shared mappings may have changed them.
+pub fn read(node: NodeId, offset: usize, buffer: &mut [u8]) {
+	for_each_cached(node, offset, buffer.len(), |position, physical_address, page_offset, length| {
+		let target = &mut buffer[position - offset..position - offset + length];
+
+		with_frame(physical_address, |page| target.copy_from_slice(&page[page_offset..page_offset + length]));
+	});
+}
+
+/// Copies `buffer`, written at `offset` of `node`, into the cached pages.
+pub fn write(node: NodeId, offset: usize, buffer: &[u8]) {
+	for_each_cached(node, offset, buffer.len(), |position, physical_address, page_offset, length| {
+		let source = &buffer[position - offset..position - offset + length];
+
+		with_frame(physical_address, |page| page[page_offset..page_offset + length].copy_from_slice(source));
+	});
+}
+
+/// Zeroes the cached pages of `node` after its data has been truncated.
+/// The frames stay in the cache, so that shared mappings of them and later
+/// reads and writes keep referring to the same pages.
+pub fn truncate(node: NodeId) {
+	for_each_cached(node, 0, usize::MAX, |_, physical_address, _, _| {
+		with_frame(physical_address, |page| page.fill(0));
+	});
+}
+
+/// Drops the cached pages of `node`. Pages still mapped by a process stay
+/// allocated until they are unmapped.
+pub fn evict(node: NodeId) {
+	let mut cache = PAGE_CACHE.lock();
+	let pages: Vec<(NodeId, usize)> = cache.range((node, 0)..=(node, usize::MAX)).map(|(key, _)| *key).collect();
+
+	for key in pages {
+		if let Some(physical_address) = cache.remove(&key) {
+			physical::release_frame(physical_address);
+		}
+	}
+}
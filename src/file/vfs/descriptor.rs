@@ -3,8 +3,9 @@ use {
 		error::Error, types::Metadata
 	},
 	crate::{
+		arch::memory::PhysicalAddress,
 		format::Debug,
-		scheduler::get_io_interface,
+		scheduler::{get_io_interface, remove_io_interface},
 	}
 };
 
@@ -41,6 +42,14 @@ pub trait Interface: Sync + Send + Debug {
 	fn metadata(&self) -> Result<Metadata, Error> {
 		Err(Error::NotImplemented)
 	}
+	/// Returns the page cache frame holding the page at `offset`, used to
+	/// map the file into memory
+	fn page(&self, _offset: usize) -> Result<PhysicalAddress, Error> {
+		Err(Error::NotImplemented)
+	}
+	fn is_writable(&self) -> bool {
+		false
+	}
 }
 
 bitflags! {
@@ -56,11 +65,12 @@ bitflags! {
         const NONBLOCK = 0o4000;
         const DIRECT_IO = 0o40000;
         const DIRECTORY = 0o200_000;
+        const CLOSE_ON_EXEC = 0o2_000_000;
     }
 }
 
 pub fn read(descriptor: Descriptor, buffer: &mut [u8]) -> Result<usize, Error> {
-	let object = get_io_interface(descriptor).map_err(|_| Error::IoError)?;
+	let object = get_io_interface(descriptor).map_err(|_| Error::BadFileDescriptor)?;
 	if buffer.is_empty() {
 		return Ok(0);
 	}
@@ -68,7 +78,7 @@ pub fn read(descriptor: Descriptor, buffer: &mut [u8]) -> Result<usize, Error> {
 }
 
 pub fn write(descriptor: Descriptor, buffer: &[u8]) -> Result<usize, Error> {
-	let object = get_io_interface(descriptor).map_err(|_| Error::IoError)?;
+	let object = get_io_interface(descriptor).map_err(|_| Error::BadFileDescriptor)?;
 	if buffer.is_empty() {
 		return Ok(0);
 	}
@@ -76,9 +86,18 @@ pub fn write(descriptor: Descriptor, buffer: &[u8]) -> Result<usize, Error> {
 }
 
 pub fn fstat(descriptor: Descriptor) -> Result<State, Error> {
-	get_io_interface(descriptor).map_err(|_| Error::IoError)?.fstat()
+	get_io_interface(descriptor).map_err(|_| Error::BadFileDescriptor)?.fstat()
 }
 
 pub fn metadata(descriptor: Descriptor) -> Result<Metadata, Error> {
-	get_io_interface(descriptor).map_err(|_| Error::IoError)?.metadata()
+	get_io_interface(descriptor).map_err(|_| Error::BadFileDescriptor)?.metadata()
+}
+
+pub fn seek(descriptor: Descriptor, offset: SeekFrom) -> Result<usize, Error> {
+	get_io_interface(descriptor).map_err(|_| Error::BadFileDescriptor)?.seek(offset)
+}
+
+pub fn close(descriptor: Descriptor) -> Result<(), Error> {
+	remove_io_interface(descriptor).map_err(|_| Error::BadFileDescriptor)?;
+	Ok(())
 }
\ No newline at end of file
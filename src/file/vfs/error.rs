@@ -16,6 +16,7 @@ pub enum Error {
     NotADirectory,
     IsADirectory,
     SymlinkLoop,
+    IllegalSeek,
 }
 
 impl Debug for Error {
@@ -34,6 +35,7 @@ impl Debug for Error {
             Error::NotADirectory => write!(f, "not a directory."),
             Error::IsADirectory => write!(f, "is a directory."),
             Error::SymlinkLoop => write!(f, "symlink loop detected."),
+            Error::IllegalSeek => write!(f, "illegal seek."),
         }
     }
 }
\ No newline at end of file
@@ -1,16 +1,22 @@
 use core::ops::{Deref, DerefMut};
 use {
 	super::{
+		cache::{self, NodeId},
 		descriptor::OpenOptions,
 		error::Error,
 	},
 	crate::{
+		arch::memory::{
+			paging::{BasePageSize, PageSize},
+			PhysicalAddress,
+		},
 		sync::lock::*,
 	},
 	alloc::{
 		sync::Arc,
 		vec::Vec,
 	},
+	core::cmp::min,
 };
 
 #[derive(Debug)]
@@ -84,6 +90,24 @@ impl RomHandle {
 		let guard = self.data.read();
 		guard.len()
 	}
+
+	pub fn node(&self) -> NodeId {
+		Arc::as_ptr(&self.data) as NodeId
+	}
+
+	/// Returns the page cache frame holding the page at `offset`.
+	pub fn page(&self, offset: usize) -> Result<PhysicalAddress, Error> {
+		let guard = self.data.read();
+		page(self.node(), &guard, offset)
+	}
+}
+
+impl Drop for RomHandle {
+	fn drop(&mut self) {
+		if Arc::strong_count(&self.data) == 1 {
+			cache::evict(self.node());
+		}
+	}
 }
 
 impl Clone for RomHandle {
@@ -125,6 +149,7 @@ impl RamHandle {
 			buf.len()
 		};
 		buf[0..len].clone_from_slice(&vec[pos..pos + len]);
+		cache::read(self.node(), pos, &mut buf[0..len]);
 		*pos_guard = pos + len;
 		Ok(len)
 	}
@@ -137,10 +162,15 @@ impl RamHandle {
 		let vec = guard.deref_mut();
 		let mut pos_guard = self.pos.lock();
 		let pos = *pos_guard;
+		let previous_len = vec.len();
 		if pos + buf.len() > vec.len() {
 			vec.resize(pos + buf.len(), 0);
 		}
 		vec[pos..pos + buf.len()].clone_from_slice(buf);
+		if pos > previous_len {
+			cache::write(self.node(), previous_len, &vec[previous_len..pos]);
+		}
+		cache::write(self.node(), pos, buf);
 		*pos_guard = pos + buf.len();
 		Ok(buf.len())
 	}
@@ -187,13 +217,14 @@ impl RamHandle {
 			vec.resize(pos + s.len(), 0);
 		}
 		vec[pos..pos + s.len()].clone_from_slice(s.as_bytes());
+		cache::write(self.node(), pos, s.as_bytes());
 		*pos_guard = pos + s.len();
 		Ok(())
 	}
 
 	pub fn get_handle(&self, opt: OpenOptions) -> RamHandle {
 		RamHandle {
-			writeable: opt.contains(OpenOptions::READ_WRITE),
+			writeable: opt.intersects(OpenOptions::WRITE_ONLY | OpenOptions::READ_WRITE),
 			pos: WaitLock::new(0),
 			data: self.data.clone(),
 		}
@@ -204,6 +235,34 @@ impl RamHandle {
 		let vec: &Vec<u8> = guard.deref();
 		vec.len()
 	}
+
+	pub fn is_writeable(&self) -> bool {
+		self.writeable
+	}
+
+	pub fn node(&self) -> NodeId {
+		Arc::as_ptr(&self.data) as NodeId
+	}
+
+	/// Returns the page cache frame holding the page at `offset`.
+	pub fn page(&self, offset: usize) -> Result<PhysicalAddress, Error> {
+		let guard = self.data.read();
+		page(self.node(), guard.deref(), offset)
+	}
+
+	pub fn truncate(&self) {
+		self.data.write().clear();
+		cache::truncate(self.node());
+		*self.pos.lock() = 0;
+	}
+}
+
+impl Drop for RamHandle {
+	fn drop(&mut self) {
+		if Arc::strong_count(&self.data) == 1 {
+			cache::evict(self.node());
+		}
+	}
 }
 
 impl Clone for RamHandle {
@@ -214,4 +273,19 @@ impl Clone for RamHandle {
 			data: self.data.clone(),
 		}
 	}
-}
\ No newline at end of file
+}
+
+/// Looks up the page at `offset` of `data` in the page cache, reading it in
+/// on a miss. Offsets at or past the end of the data are rejected.
+fn page(node: NodeId, data: &[u8], offset: usize) -> Result<PhysicalAddress, Error> {
+	if offset >= data.len() {
+		return Err(Error::InvalidArgument);
+	}
+
+	Ok(cache::get_page(node, offset, |page| {
+		let start = align_down!(offset, BasePageSize::SIZE);
+		let end = min(start + BasePageSize::SIZE, data.len());
+
+		page[..end - start].copy_from_slice(&data[start..end]);
+	}))
+}
@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
 pub mod system;
+pub mod cache;
 pub mod handle;
 pub mod standard;
 pub mod descriptor;
@@ -19,18 +20,23 @@ use {
 	},
 	alloc::{
 		format,
+		sync::Arc,
 		vec::Vec,
 		string::{String, ToString},
 	},
 };
 
-static DEMO: &[u8] = include_bytes!("../../../demo/hello_kernel");
+/// Static position independent build of demo/hello_2.c, which needs no
+/// interpreter:
+/// gcc -static-pie -nostdlib -fPIE -Os -fno-stack-protector -mgeneral-regs-only
+static DEMO: &[u8] = include_bytes!("../../../demo/hello_static");
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum NodeKind {
 	File,
 	Directory,
 	Symlink,
+	CharacterDevice,
 }
 
 pub fn list() -> Result<(), Error> {
@@ -46,12 +52,15 @@ pub fn open(name: &str, flags: descriptor::OpenOptions) -> Result<descriptor::De
 	debug!("open {}, {:?}.", name, flags);
 	let name = normalize_path(&name.to_string())?;
 	let fs = unsafe { system::ROOT.as_mut().unwrap() };
-	if let Ok(file) = fs.open(&name, flags) {
-		let fd = insert_io_interface(file).map_err(|_| Error::IoError)?;
-		Ok(fd)
-	} else {
-		Err(Error::InvalidArgument)
-	}
+	let file = fs.open(&name, flags)?;
+	let fd = insert_io_interface(file).map_err(|_| Error::IoError)?;
+	Ok(fd)
+}
+
+pub fn lookup(name: &str) -> Result<Arc<dyn descriptor::Interface>, Error> {
+	let name = normalize_path(&name.to_string())?;
+	let fs = unsafe { system::ROOT.as_mut().unwrap() };
+	fs.open(&name, descriptor::OpenOptions::READ_ONLY)
 }
 
 pub fn mount(path: &String, slice: &'static [u8]) -> Result<(), Error> {
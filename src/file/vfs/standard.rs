@@ -5,15 +5,40 @@ use crate::arch::vga;
 
 use {
 	super::{
+		NodeKind,
 		error::Error,
-		descriptor::Interface,
-	}
+		descriptor::{Interface, SeekFrom, State},
+		types::{Metadata, Permission},
+	},
+	crate::arch::kernel::devices::serial as input,
 };
 
+fn terminal_metadata() -> Metadata {
+	let mut metadata = Metadata::new(NodeKind::CharacterDevice);
+	metadata.permission = Permission::from_mode(0o620);
+	metadata
+}
+
 #[derive(Debug)]
 pub struct GenericStandardInput;
 
-impl Interface for GenericStandardInput {}
+impl Interface for GenericStandardInput {
+	fn read(&self, buf: &mut [u8]) -> Result<usize, Error> {
+		Ok(input::read(buf))
+	}
+
+	fn seek(&self, _offset: SeekFrom) -> Result<usize, Error> {
+		Err(Error::IllegalSeek)
+	}
+
+	fn fstat(&self) -> Result<State, Error> {
+		Ok(State { size: 0 })
+	}
+
+	fn metadata(&self) -> Result<Metadata, Error> {
+		Ok(terminal_metadata())
+	}
+}
 
 impl GenericStandardInput {
 	pub const fn new() -> Self {
@@ -35,6 +60,18 @@ impl Interface for GenericStandardOutput {
         }
 		Ok(buf.len())
 	}
+
+	fn seek(&self, _offset: SeekFrom) -> Result<usize, Error> {
+		Err(Error::IllegalSeek)
+	}
+
+	fn fstat(&self) -> Result<State, Error> {
+		Ok(State { size: 0 })
+	}
+
+	fn metadata(&self) -> Result<Metadata, Error> {
+		Ok(terminal_metadata())
+	}
 }
 
 impl GenericStandardOutput {
@@ -57,6 +94,18 @@ impl Interface for GenericStandardError {
         }
 		Ok(buf.len())
 	}
+
+	fn seek(&self, _offset: SeekFrom) -> Result<usize, Error> {
+		Err(Error::IllegalSeek)
+	}
+
+	fn fstat(&self) -> Result<State, Error> {
+		Ok(State { size: 0 })
+	}
+
+	fn metadata(&self) -> Result<Metadata, Error> {
+		Ok(terminal_metadata())
+	}
 }
 
 impl GenericStandardError {
@@ -7,6 +7,7 @@ use {
 		types::{Permission, Metadata},
 	},
 	crate::{
+		arch::memory::PhysicalAddress,
 		format::{
 			self, Debug,
 		},
@@ -129,9 +130,17 @@ impl Directory {
 			}
 			if components.is_empty() {
 				if let Some(file) = self.get_mut::<File>(&node_name) {
+					if flags.contains(OpenOptions::CREATE | OpenOptions::EXCLUSIVE) {
+						return Err(Error::AlreadyExists);
+					}
  				if !file.get_metadata().permission.can_read() {
 						return Err(Error::PermissionDenied);
 					}
+					if flags.contains(OpenOptions::TRUNCATE)
+						&& flags.intersects(OpenOptions::WRITE_ONLY | OpenOptions::READ_WRITE)
+					{
+						file.truncate()?;
+					}
 					return file.get_handle(flags);
 				}
 				let symlink_target = self.get::<SymbolLink>(&node_name).map(|symlink| {
@@ -323,6 +332,16 @@ impl Node for File {
 }
 
 impl File {
+	fn truncate(&self) -> Result<(), Error> {
+		match self.data {
+			DataHandle::RAM(ref data) => {
+				data.truncate();
+				Ok(())
+			}
+			DataHandle::ROM(_) => Err(Error::PermissionDenied),
+		}
+	}
+
 	fn get_handle(&self, opt: OpenOptions) -> Result<Arc<dyn Interface>, Error> {
 		match self.data {
 			DataHandle::RAM(ref data) => Ok(Arc::new(File {
@@ -379,6 +398,20 @@ impl Interface for File {
 	fn metadata(&self) -> Result<Metadata, Error> {
 		Ok(self.metadata)
 	}
+
+	fn page(&self, offset: usize) -> Result<PhysicalAddress, Error> {
+		match self.data {
+			DataHandle::RAM(ref data) => data.page(offset),
+			DataHandle::ROM(ref data) => data.page(offset),
+		}
+	}
+
+	fn is_writable(&self) -> bool {
+		match self.data {
+			DataHandle::RAM(ref data) => data.is_writeable(),
+			DataHandle::ROM(_) => false,
+		}
+	}
 }
 
 #[derive(Debug)]
@@ -0,0 +1,31 @@
+use {
+	super::{copy_path, copy_strings},
+	crate::{
+		error::Errno,
+		io,
+	},
+};
+
+pub extern "C" fn execute(path: *const u8, argv: *const *const u8, envp: *const *const u8) -> isize {
+	debug!("enter invoke execute.");
+
+	let path = match copy_path(path) {
+		Ok(path) => path,
+		Err(error) => return error.to_return(),
+	};
+
+	let argv = match copy_strings(argv) {
+		Ok(argv) => argv,
+		Err(error) => return error.to_return(),
+	};
+
+	let envp = match copy_strings(envp) {
+		Ok(envp) => envp,
+		Err(error) => return error.to_return(),
+	};
+
+	match io::execute(&path, &argv, &envp) {
+		Ok(_) => 0,
+		Err(error) => error.to_return(),
+	}
+}
@@ -1,7 +1,14 @@
 use crate::scheduler;
 
-pub extern "C" fn exit() {
+pub extern "C" fn exit(status: i32) {
 	debug!("enter invoke exit.");
 	
-	scheduler::exit();
-}
\ No newline at end of file
+	scheduler::exit(status);
+}
+
+/// Exits all threads of the current process.
+pub extern "C" fn exit_group(status: i32) {
+	debug!("enter invoke exit_group.");
+
+	scheduler::exit_group(status);
+}
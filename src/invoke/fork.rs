@@ -0,0 +1,126 @@
+use {
+	crate::{
+		arch::{
+			kernel::invoke::handler::saved_user_stack_pointer,
+			memory::{
+				user::{copy_from_user, copy_to_user, put_user},
+				VirtualAddress,
+			},
+		},
+		error::{numbers, Errno},
+		scheduler::{self, save_interrupt, task::TaskId},
+	},
+	super::thread::check_base,
+};
+
+/// Number of user registers `invoke_handler` pushes onto the user stack
+const SAVED_REGISTERS: usize = 14;
+
+/// Low byte of the clone flags, the signal sent to the parent on exit
+const CLONE_SIGNAL_MASK: u64 = 0xff;
+
+const CLONE_VM: u64 = 0x100;
+const CLONE_FS: u64 = 0x200;
+const CLONE_FILES: u64 = 0x400;
+const CLONE_SIGHAND: u64 = 0x800;
+const CLONE_THREAD: u64 = 0x10000;
+const CLONE_SYSVSEM: u64 = 0x40000;
+const CLONE_SETTLS: u64 = 0x80000;
+const CLONE_PARENT_SETTID: u64 = 0x100000;
+const CLONE_CHILD_CLEARTID: u64 = 0x200000;
+const CLONE_DETACHED: u64 = 0x400000;
+const CLONE_CHILD_SETTID: u64 = 0x1000000;
+
+/// Flags creating a thread, which shares the whole process of its parent
+const THREAD_FLAGS: u64 = CLONE_VM | CLONE_FILES | CLONE_SIGHAND | CLONE_THREAD;
+
+/// Flags accepted together with `THREAD_FLAGS`
+const THREAD_OPTIONS: u64 = CLONE_FS
+	| CLONE_SYSVSEM
+	| CLONE_SETTLS
+	| CLONE_PARENT_SETTID
+	| CLONE_CHILD_CLEARTID
+	| CLONE_DETACHED
+	| CLONE_CHILD_SETTID;
+
+fn fork_at(user_stack_pointer: usize) -> isize {
+	scheduler::fork(VirtualAddress::from_usize(user_stack_pointer)).map_or_else(
+		|error| error.to_return(),
+		|pid| pid.into() as isize,
+	)
+}
+
+pub extern "C" fn fork() -> isize {
+	debug!("enter invoke fork.");
+
+	fork_at(saved_user_stack_pointer())
+}
+
+/// Copies the user registers saved by the current invoke below `stack`, so
+/// that a child can restore them from there, and returns their address.
+fn child_stack(stack: usize) -> Result<usize, i32> {
+	let mut registers = [0u8; SAVED_REGISTERS * 8];
+	let child_stack_pointer = stack.checked_sub(registers.len()).ok_or(numbers::BAD_ADDRESS)?;
+
+	copy_from_user(&mut registers, saved_user_stack_pointer() as *const u8)?;
+	copy_to_user(child_stack_pointer as *mut u8, &registers)?;
+
+	Ok(child_stack_pointer)
+}
+
+/// Creates a thread of the current process running on `stack`.
+fn clone_thread(flags: u64, stack: usize, parent_tid: *mut u32, child_tid: *mut u32, tls: usize) -> Result<TaskId, i32> {
+	if flags & !(THREAD_FLAGS | THREAD_OPTIONS | CLONE_SIGNAL_MASK) != 0 || stack == 0 {
+		return Err(numbers::INVALID_ARGUMENT);
+	}
+
+	let thread_pointer = if flags & CLONE_SETTLS != 0 { Some(check_base(tls)?) } else { None };
+	let clear_child_tid = if flags & CLONE_CHILD_CLEARTID != 0 { child_tid as usize } else { 0 };
+	let user_stack_pointer = child_stack(stack)?;
+
+	// the thread ids are stored before the new thread gets to run
+	save_interrupt(|| {
+		let tid = scheduler::spawn_thread(
+			VirtualAddress::from_usize(user_stack_pointer),
+			thread_pointer,
+			VirtualAddress::from_usize(clear_child_tid),
+		)
+		.map_err(|error| error.errno())?;
+
+		if flags & CLONE_PARENT_SETTID != 0 {
+			put_user(parent_tid, tid.into())?;
+		}
+
+		if flags & CLONE_CHILD_SETTID != 0 {
+			put_user(child_tid, tid.into())?;
+		}
+
+		Ok(tid)
+	})
+}
+
+/// Supports the fork-like subset of clone, optionally with a separate stack
+/// for the child, and the creation of threads.
+pub extern "C" fn clone(flags: u64, stack: usize, parent_tid: *mut u32, child_tid: *mut u32, tls: usize) -> isize {
+	debug!("enter invoke clone.");
+
+	if flags & THREAD_FLAGS == THREAD_FLAGS {
+		return clone_thread(flags, stack, parent_tid, child_tid, tls)
+			.map_or_else(|error| error.to_return(), |tid| tid.into() as isize);
+	}
+
+	if flags & !CLONE_SIGNAL_MASK != 0 {
+		return numbers::INVALID_ARGUMENT.to_return();
+	}
+
+	if stack == 0 {
+		return fork_at(saved_user_stack_pointer());
+	}
+
+	// the child restores its registers from the new stack, so they are
+	// placed below it before the address space is duplicated
+	match child_stack(stack) {
+		Ok(child_stack_pointer) => fork_at(child_stack_pointer),
+		Err(error) => error.to_return(),
+	}
+}
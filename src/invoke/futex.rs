@@ -0,0 +1,63 @@
+use {
+	crate::{
+		arch::memory::user::{get_user, user_to_physical},
+		error::{numbers, Errno},
+		scheduler::{self, save_interrupt},
+	},
+	core::mem::size_of,
+};
+
+const FUTEX_WAIT: i32 = 0;
+const FUTEX_WAKE: i32 = 1;
+
+/// The futex is only used by threads of one process. Waiters are keyed by
+/// frame in both cases, so the flag makes no difference.
+const FUTEX_PRIVATE_FLAG: i32 = 128;
+
+/// The timeout is measured against the realtime clock
+const FUTEX_CLOCK_REALTIME: i32 = 256;
+
+/// Blocks the current task if the word at `address` still holds `value`.
+fn wait(address: usize, value: u32) -> Result<usize, i32> {
+	let key = user_to_physical(address)?;
+
+	// the check and the queueing must not be separated by a wake
+	save_interrupt(|| {
+		if get_user(address as *const u32)? != value {
+			return Err(numbers::TRY_AGAIN);
+		}
+
+		scheduler::futex_wait(key);
+
+		Ok(())
+	})?;
+
+	scheduler::reschedule();
+
+	Ok(0)
+}
+
+fn wake(address: usize, count: u32) -> Result<usize, i32> {
+	let key = user_to_physical(address)?;
+
+	Ok(scheduler::futex_wake(key, count as usize))
+}
+
+/// Supports FUTEX_WAIT and FUTEX_WAKE. Waiting with a timeout is not
+/// supported yet.
+pub extern "C" fn futex(address: usize, operation: i32, value: u32, timeout: usize) -> isize {
+	debug!("enter invoke futex.");
+
+	if address % size_of::<u32>() != 0 {
+		return numbers::INVALID_ARGUMENT.to_return();
+	}
+
+	let result = match operation & !(FUTEX_PRIVATE_FLAG | FUTEX_CLOCK_REALTIME) {
+		FUTEX_WAIT if timeout != 0 => Err(numbers::NOT_IMPLEMENTED),
+		FUTEX_WAIT => wait(address, value),
+		FUTEX_WAKE => wake(address, value),
+		_ => Err(numbers::NOT_IMPLEMENTED),
+	};
+
+	result.map_or_else(|error| error.to_return(), |woken| woken as isize)
+}
@@ -8,7 +8,7 @@ use {
 extern "C" fn invalid_invoke(sys_no: u64) -> ! {
 	error!("invalid invoke {}.", sys_no);
 	
-	exit();
+	exit(-1);
 }
 
 #[allow(unused_assignments)]
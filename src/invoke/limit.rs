@@ -0,0 +1,105 @@
+use {
+	crate::{
+		arch::memory::user::{get_user, put_user},
+		consts::USER_STACK_MAX,
+		error::{numbers, Errno},
+		scheduler::get_current_process,
+	},
+};
+
+/// Maximum size of the process stack
+const RLIMIT_STACK: u32 = 3;
+
+/// Number of resources known to Linux
+const RLIMIT_COUNT: u32 = 16;
+
+/// Value of a limit which is not enforced
+const RLIM_INFINITY: u64 = u64::MAX;
+
+/// Soft and hard limit of a resource as used by getrlimit() on Linux x86-64
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ResourceLimit {
+	pub current: u64,
+	pub maximum: u64,
+}
+
+/// Returns the limits of `resource`. Only the stack is limited, every
+/// other resource reports no limit.
+fn get_limit(resource: u32) -> Result<ResourceLimit, i32> {
+	match resource {
+		RLIMIT_STACK => Ok(ResourceLimit {
+			current: get_current_process().borrow().address_space.stack_limit as u64,
+			maximum: USER_STACK_MAX as u64,
+		}),
+		resource if resource < RLIMIT_COUNT => Ok(ResourceLimit {
+			current: RLIM_INFINITY,
+			maximum: RLIM_INFINITY,
+		}),
+		_ => Err(numbers::INVALID_ARGUMENT),
+	}
+}
+
+/// Sets the limits of `resource`. The stack limit applies to the stack
+/// reserved by the next execve and cannot exceed `USER_STACK_MAX`.
+fn set_limit(resource: u32, limit: ResourceLimit) -> Result<(), i32> {
+	if limit.current > limit.maximum {
+		return Err(numbers::INVALID_ARGUMENT);
+	}
+
+	match resource {
+		RLIMIT_STACK => {
+			// the hard limit is fixed by the layout of the address space
+			if limit.maximum > USER_STACK_MAX as u64 {
+				return Err(numbers::OPERATION_NOT_PERMITTED);
+			}
+
+			get_current_process().borrow_mut().address_space.stack_limit = limit.current as usize;
+
+			Ok(())
+		}
+		// limits of the other resources are accepted but not enforced
+		resource if resource < RLIMIT_COUNT => Ok(()),
+		_ => Err(numbers::INVALID_ARGUMENT),
+	}
+}
+
+pub extern "C" fn get_resource_limit(resource: u32, limit: *mut ResourceLimit) -> isize {
+	debug!("enter invoke getrlimit.");
+
+	get_limit(resource)
+		.and_then(|value| put_user(limit, value))
+		.map_or_else(|error| error.to_return(), |_| 0)
+}
+
+pub extern "C" fn set_resource_limit(resource: u32, limit: *const ResourceLimit) -> isize {
+	debug!("enter invoke setrlimit.");
+
+	get_user(limit)
+		.and_then(|value| set_limit(resource, value))
+		.map_or_else(|error| error.to_return(), |_| 0)
+}
+
+/// Combines getrlimit and setrlimit. Only the calling process, selected by
+/// pid 0 or its own pid, can be queried or changed.
+pub extern "C" fn process_resource_limit(pid: i32, resource: u32, new_limit: *const ResourceLimit, old_limit: *mut ResourceLimit) -> isize {
+	debug!("enter invoke prlimit64.");
+
+	if pid != 0 && pid as u32 != get_current_process().borrow().id.into() {
+		return numbers::PROCESS_NOT_FOUND.to_return();
+	}
+
+	let result = get_limit(resource).and_then(|old| {
+		if !new_limit.is_null() {
+			set_limit(resource, get_user(new_limit)?)?;
+		}
+
+		if !old_limit.is_null() {
+			put_user(old_limit, old)?;
+		}
+
+		Ok(())
+	});
+
+	result.map_or_else(|error| error.to_return(), |_| 0)
+}
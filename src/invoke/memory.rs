@@ -0,0 +1,271 @@
+use {
+	crate::{
+		arch::memory::{
+			paging::{protect_pages, unmap_pages, BasePageSize, PageSize, PageTableEntryFlags},
+			r#virtual::TASK_VIRTUAL_MEMORY_END,
+			VirtualAddress,
+		},
+		error::{numbers, Errno},
+		file::vfs::{descriptor::Descriptor, NodeKind},
+		scheduler::{
+			get_current_process, get_io_interface,
+			process::{AddressSpace, FileMapping, MemoryRegion},
+		},
+	},
+	core::cmp::{max, min, Ordering},
+};
+
+const PROT_READ: i32 = 0x1;
+const PROT_WRITE: i32 = 0x2;
+const PROT_EXEC: i32 = 0x4;
+
+/// Changes are shared with other mappings of the same object
+const MAP_SHARED: i32 = 0x01;
+
+/// Changes are private to the process and copied on write after a fork
+const MAP_PRIVATE: i32 = 0x02;
+
+/// Place the mapping exactly at the given address, replacing older mappings
+const MAP_FIXED: i32 = 0x10;
+
+/// The mapping is not backed by a file and filled with zeros
+const MAP_ANONYMOUS: i32 = 0x20;
+
+/// Translates mmap protection bits into the flags of a region. Without any
+/// bit the pages stay present but inaccessible to user space.
+fn protection_flags(protection: i32) -> Result<PageTableEntryFlags, i32> {
+	if protection & !(PROT_READ | PROT_WRITE | PROT_EXEC) != 0 {
+		return Err(numbers::INVALID_ARGUMENT);
+	}
+
+	let mut flags = PageTableEntryFlags::empty();
+
+	// x86 pages are readable whenever they are writable or executable
+	if protection != 0 {
+		flags |= PageTableEntryFlags::USER_ACCESSIBLE;
+	}
+
+	if protection & PROT_WRITE != 0 {
+		flags |= PageTableEntryFlags::WRITABLE;
+	}
+
+	if protection & PROT_EXEC == 0 {
+		flags |= PageTableEntryFlags::EXECUTE_DISABLE;
+	}
+
+	Ok(flags)
+}
+
+/// Returns the pages covering `length` bytes at the page aligned `address`.
+fn page_range(address: usize, length: usize) -> Result<(VirtualAddress, VirtualAddress), i32> {
+	if address % BasePageSize::SIZE != 0 || length == 0 {
+		return Err(numbers::INVALID_ARGUMENT);
+	}
+
+	let end = address
+		.checked_add(length)
+		.and_then(|end| end.checked_add(BasePageSize::SIZE - 1))
+		.ok_or(numbers::OUT_OF_MEMORY)?;
+
+	Ok((VirtualAddress::from_usize(address), VirtualAddress::from_usize(align_down!(end, BasePageSize::SIZE))))
+}
+
+/// Looks up the file a mapping of `descriptor` at `offset` is backed by.
+fn file_mapping(descriptor: Descriptor, offset: isize, shared: bool, protection: i32) -> Result<FileMapping, i32> {
+	if offset < 0 || offset as usize % BasePageSize::SIZE != 0 {
+		return Err(numbers::INVALID_ARGUMENT);
+	}
+
+	let file = get_io_interface(descriptor).map_err(|_| numbers::BAD_FILE_DESCRIPTOR)?;
+
+	if !matches!(file.metadata(), Ok(metadata) if metadata.kind == NodeKind::File) {
+		return Err(numbers::NO_SUCH_DEVICE);
+	}
+
+	// shared writes reach the file, which requires a writable descriptor
+	if shared && protection & PROT_WRITE != 0 && !file.is_writable() {
+		return Err(numbers::PERMISSION_DENIED);
+	}
+
+	Ok(FileMapping {
+		file,
+		offset: offset as usize,
+		shared,
+	})
+}
+
+/// Removes `[start, end)` from the address space and releases the frames of
+/// the pages faulted in there.
+fn release(address_space: &mut AddressSpace, start: VirtualAddress, end: VirtualAddress) {
+	for region in address_space.regions.iter().filter(|region| region.overlaps(start, end)) {
+		unmap_pages(max(region.start, start), min(region.end, end));
+	}
+
+	address_space.remove_region(start, end);
+}
+
+/// Maps anonymous memory or a file into the address space.
+///
+/// Shared anonymous mappings are not supported and fail with EOPNOTSUPP,
+/// because their pages would need an object to stay shared across fork.
+pub extern "C" fn map_memory(address: usize, length: usize, protection: i32, flags: i32, descriptor: Descriptor, offset: isize) -> isize {
+	debug!("enter invoke mmap.");
+
+	let page_flags = match protection_flags(protection) {
+		Ok(page_flags) => page_flags,
+		Err(error) => return error.to_return(),
+	};
+
+	let shared = match flags & (MAP_SHARED | MAP_PRIVATE) {
+		MAP_SHARED => true,
+		MAP_PRIVATE => false,
+		_ => return numbers::INVALID_ARGUMENT.to_return(),
+	};
+
+	let mapping = if flags & MAP_ANONYMOUS != 0 {
+		// shared anonymous memory has no page cache to keep the pages of
+		// all processes in
+		if shared {
+			return numbers::OPERATION_NOT_SUPPORTED.to_return();
+		}
+
+		None
+	} else {
+		match file_mapping(descriptor, offset, shared, protection) {
+			Ok(mapping) => Some(mapping),
+			Err(error) => return error.to_return(),
+		}
+	};
+
+	let (start, end) = match page_range(align_down!(address, BasePageSize::SIZE), length) {
+		Ok(range) => range,
+		Err(error) => return error.to_return(),
+	};
+
+	let length = (end - start).as_usize();
+	let process = get_current_process();
+	let mut process = process.borrow_mut();
+	let address_space = &mut process.address_space;
+
+	let start = if flags & MAP_FIXED != 0 {
+		if start.as_usize() != address {
+			return numbers::INVALID_ARGUMENT.to_return();
+		}
+
+		if !AddressSpace::is_user_range(start, end) {
+			return numbers::OUT_OF_MEMORY.to_return();
+		}
+
+		release(address_space, start, end);
+		start
+	} else {
+		match address_space.find_free_range(start, length) {
+			Some(start) => start,
+			None => return numbers::OUT_OF_MEMORY.to_return(),
+		}
+	};
+
+	debug!("map memory at 0x{:x} ({} bytes).", start, length);
+
+	// pages are only populated when they are first touched
+	address_space.insert_region(match mapping {
+		Some(mapping) => MemoryRegion::new_file(start, start + length, page_flags, mapping),
+		None => MemoryRegion::new(start, start + length, page_flags),
+	});
+
+	start.as_usize() as isize
+}
+
+pub extern "C" fn unmap_memory(address: usize, length: usize) -> isize {
+	debug!("enter invoke munmap.");
+
+	let (start, end) = match page_range(address, length) {
+		Ok(range) => range,
+		Err(error) => return error.to_return(),
+	};
+
+	if end > TASK_VIRTUAL_MEMORY_END {
+		return numbers::INVALID_ARGUMENT.to_return();
+	}
+
+	let process = get_current_process();
+	release(&mut process.borrow_mut().address_space, start, end);
+
+	0
+}
+
+pub extern "C" fn protect_memory(address: usize, length: usize, protection: i32) -> isize {
+	debug!("enter invoke mprotect.");
+
+	let flags = match protection_flags(protection) {
+		Ok(flags) => flags,
+		Err(error) => return error.to_return(),
+	};
+
+	let (start, end) = match page_range(address, length) {
+		Ok(range) => range,
+		Err(error) => return error.to_return(),
+	};
+
+	let process = get_current_process();
+	let mut process = process.borrow_mut();
+	let address_space = &mut process.address_space;
+
+	if !address_space.is_mapped(start, end) {
+		return numbers::OUT_OF_MEMORY.to_return();
+	}
+
+	let is_read_only_file = |region: &MemoryRegion| {
+		region.mapping.as_ref().is_some_and(|mapping| mapping.shared && !mapping.file.is_writable())
+	};
+
+	if protection & PROT_WRITE != 0 && address_space.regions.iter().any(|region| region.overlaps(start, end) && is_read_only_file(region)) {
+		return numbers::PERMISSION_DENIED.to_return();
+	}
+
+	address_space.protect_region(start, end, flags);
+	protect_pages(start, end, flags);
+
+	0
+}
+
+/// Moves the end of the heap to `address` and returns the new end, or the
+/// unchanged one if the heap cannot be moved there.
+pub extern "C" fn set_break(address: usize) -> isize {
+	debug!("enter invoke brk.");
+
+	let process = get_current_process();
+	let mut process = process.borrow_mut();
+	let address_space = &mut process.address_space;
+	let current = address_space.program_break;
+
+	if address < address_space.heap_start.as_usize() {
+		return current.as_usize() as isize;
+	}
+
+	let current_end = VirtualAddress::from_usize(align_up!(current.as_usize(), BasePageSize::SIZE));
+	let end = match address.checked_add(BasePageSize::SIZE - 1) {
+		Some(end) => VirtualAddress::from_usize(align_down!(end, BasePageSize::SIZE)),
+		None => return current.as_usize() as isize,
+	};
+
+	match end.cmp(&current_end) {
+		Ordering::Greater => {
+			if !AddressSpace::is_user_range(current_end, end) || !address_space.is_free(current_end, end) {
+				return current.as_usize() as isize;
+			}
+
+			address_space.insert_region(MemoryRegion::new(
+				current_end,
+				end,
+				PageTableEntryFlags::WRITABLE | PageTableEntryFlags::USER_ACCESSIBLE | PageTableEntryFlags::EXECUTE_DISABLE,
+			));
+		}
+		Ordering::Less => release(address_space, end, current_end),
+		Ordering::Equal => {}
+	}
+
+	address_space.program_break = VirtualAddress::from_usize(address);
+
+	address as isize
+}
@@ -1,42 +1,132 @@
+mod execute;
 mod exit;
+mod fork;
+mod futex;
 mod invalid;
+mod limit;
+mod memory;
 mod nothing;
+mod open;
+mod read;
+mod seek;
+mod stat;
+mod thread;
+mod time;
+mod wait;
 mod write;
 
 use {
-	exit::exit,
+	crate::{
+		error,
+		io::ARGUMENT_MAX,
+		arch::memory::user::{get_user, strncpy_from_user},
+	},
+	execute::execute,
+	exit::{exit, exit_group},
+	fork::{clone, fork},
+	futex::futex,
 	invalid::invalid,
+	limit::{get_resource_limit, process_resource_limit, set_resource_limit},
+	memory::{map_memory, protect_memory, set_break, unmap_memory},
 	nothing::nothing,
+	open::{open, close},
+	read::read,
+	seek::seek,
+	stat::{file_status, file_status_at},
+	thread::{arch_process_control, set_thread_id_address},
+	time::{clock_get_time, get_time_of_day, sleep},
+	wait::wait,
 	write::{write, write_vector},
+	alloc::{string::String, vec, vec::Vec},
 };
 
 pub mod numbers {
 	// These match the Linux x86-64 invoke numbers for compatibility
 
+	/// System invoke number for read() - read data from file descriptor
+	pub const READ: usize = 0;
+
 	/// System invoke number for write() - output data to file descriptor
 	pub const WRITE: usize = 1;
 
+	/// System invoke number for open() - open file and return descriptor
+	pub const OPEN: usize = 2;
+
 	/// System invoke number for close() - close file descriptor
 	pub const CLOSE: usize = 3;
 
+	/// System invoke number for fstat() - get file status by descriptor
+	pub const FILE_STATUS: usize = 5;
+
+	/// System invoke number for lseek() - reposition file offset
+	pub const SEEK: usize = 8;
+
+	/// System invoke number for mmap() - map memory into the address space
+	pub const MEMORY_MAP: usize = 9;
+
+	/// System invoke number for mprotect() - change the protection of mapped memory
+	pub const MEMORY_PROTECT: usize = 10;
+
+	/// System invoke number for munmap() - remove a memory mapping
+	pub const MEMORY_UNMAP: usize = 11;
+
+	/// System invoke number for brk() - move the end of the heap
+	pub const BREAK: usize = 12;
+
 	/// System invoke number for ioctl() - device-specific input/output control
 	pub const IO_CONTROL: usize = 16;
 
 	/// System invoke number for writev() - write data from multiple buffers
 	pub const WRITE_VECTOR: usize = 20;
 
+	/// System invoke number for nanosleep() - suspend the calling thread
+	pub const SLEEP: usize = 35;
+
+	/// System invoke number for clone() - create a child process
+	pub const CLONE: usize = 56;
+
+	/// System invoke number for fork() - duplicate the calling process
+	pub const FORK: usize = 57;
+
+	/// System invoke number for execve() - replace the current process image
+	pub const EXECUTE: usize = 59;
+
 	/// System invoke number for exit() - terminate calling process
 	pub const EXIT: usize = 60;
 
+	/// System invoke number for wait4() - wait for a child process to exit
+	pub const WAIT: usize = 61;
+
+	/// System invoke number for gettimeofday() - get the time since the epoch
+	pub const GET_TIME_OF_DAY: usize = 96;
+
+	/// System invoke number for getrlimit() - get resource limits
+	pub const GET_RESOURCE_LIMIT: usize = 97;
+
 	/// System invoke number for arch_prctl() - set architecture-specific thread state
 	pub const ARCH_PROCESS_CONTROL: usize = 158;
 
+	/// System invoke number for setrlimit() - set resource limits
+	pub const SET_RESOURCE_LIMIT: usize = 160;
+
+	/// System invoke number for futex() - wait on or wake a user space lock word
+	pub const FUTEX: usize = 202;
+
 	/// System invoke number for set_tid_address() - set pointer to thread ID
 	pub const SET_THREAD_ID_ADDRESS: usize = 218;
 
+	/// System invoke number for clock_gettime() - read a clock
+	pub const CLOCK_GET_TIME: usize = 228;
+
 	/// System invoke number for exit_group() - exit all threads in a process
 	pub const EXIT_GROUP: usize = 231;
 
+	/// System invoke number for newfstatat() - get file status by path
+	pub const FILE_STATUS_AT: usize = 262;
+
+	/// System invoke number for prlimit64() - get and set resource limits
+	pub const PROCESS_RESOURCE_LIMIT: usize = 302;
+
 	/// Total number of possible system invoke in the table
 	pub const MAX_INVOKES: usize = 400;
 }
@@ -53,17 +143,41 @@ impl InvokeTable {
 			handle: [invalid as *const _; numbers::MAX_INVOKES],
 		};
 
+		table.handle[numbers::READ] = read as *const _;
 		table.handle[numbers::WRITE] = write as *const _;             
 		table.handle[numbers::WRITE_VECTOR] = write_vector as *const _;  
 
-		table.handle[numbers::CLOSE] = nothing as *const _;     
+		table.handle[numbers::OPEN] = open as *const _;
+		table.handle[numbers::CLOSE] = close as *const _;
+		table.handle[numbers::SEEK] = seek as *const _;
+		table.handle[numbers::FILE_STATUS] = file_status as *const _;
+		table.handle[numbers::FILE_STATUS_AT] = file_status_at as *const _;
+
+		table.handle[numbers::MEMORY_MAP] = map_memory as *const _;
+		table.handle[numbers::MEMORY_PROTECT] = protect_memory as *const _;
+		table.handle[numbers::MEMORY_UNMAP] = unmap_memory as *const _;
+		table.handle[numbers::BREAK] = set_break as *const _;
+
 		table.handle[numbers::IO_CONTROL] = nothing as *const _;  
 
+		table.handle[numbers::CLONE] = clone as *const _;
+		table.handle[numbers::FORK] = fork as *const _;
+		table.handle[numbers::EXECUTE] = execute as *const _;
 		table.handle[numbers::EXIT] = exit as *const _;  
-		table.handle[numbers::EXIT_GROUP] = exit as *const _; 
+		table.handle[numbers::EXIT_GROUP] = exit_group as *const _; 
+		table.handle[numbers::WAIT] = wait as *const _;
+
+		table.handle[numbers::GET_RESOURCE_LIMIT] = get_resource_limit as *const _;
+		table.handle[numbers::SET_RESOURCE_LIMIT] = set_resource_limit as *const _;
+		table.handle[numbers::PROCESS_RESOURCE_LIMIT] = process_resource_limit as *const _;
 
-		table.handle[numbers::ARCH_PROCESS_CONTROL] = nothing as *const _;  
-		table.handle[numbers::SET_THREAD_ID_ADDRESS] = nothing as *const _;   
+		table.handle[numbers::ARCH_PROCESS_CONTROL] = arch_process_control as *const _;
+		table.handle[numbers::SET_THREAD_ID_ADDRESS] = set_thread_id_address as *const _;
+		table.handle[numbers::FUTEX] = futex as *const _;
+
+		table.handle[numbers::SLEEP] = sleep as *const _;
+		table.handle[numbers::GET_TIME_OF_DAY] = get_time_of_day as *const _;
+		table.handle[numbers::CLOCK_GET_TIME] = clock_get_time as *const _;
 
 		table
 	}
@@ -79,4 +193,51 @@ impl Default for InvokeTable {
 	}
 }
 
-pub static INVOKE_TABLE: InvokeTable = InvokeTable::default();
\ No newline at end of file
+pub static INVOKE_TABLE: InvokeTable = InvokeTable::default();
+
+/// Longest path accepted from user space, including the terminating null byte
+const PATH_MAX: usize = 4096;
+
+/// Largest number of bytes moved between user and kernel memory at once
+const TRANSFER_SIZE: usize = 0x10000;
+
+/// Copies a null-terminated string of less than `limit` bytes out of user memory
+fn copy_string(pointer: *const u8, limit: usize, error: i32) -> Result<String, i32> {
+	let mut bytes = vec![0u8; limit];
+	let length = strncpy_from_user(&mut bytes, pointer)?;
+
+	if length == limit {
+		return Err(error);
+	}
+
+	bytes.truncate(length);
+	String::from_utf8(bytes).map_err(|_| error::numbers::INVALID_ARGUMENT)
+}
+
+/// Copies a null-terminated path out of user memory
+fn copy_path(pointer: *const u8) -> Result<String, i32> {
+	copy_string(pointer, PATH_MAX, error::numbers::FILENAME_TOO_LONG)
+}
+
+/// Copies a null-terminated array of strings, such as argv or envp, out of
+/// user memory. A null array is treated as an empty one.
+fn copy_strings(pointer: *const *const u8) -> Result<Vec<String>, i32> {
+	let mut strings: Vec<String> = Vec::new();
+	let mut total = 0;
+
+	if pointer.is_null() {
+		return Ok(strings);
+	}
+
+	loop {
+		let string = get_user(pointer.wrapping_add(strings.len()))?;
+
+		if string.is_null() {
+			return Ok(strings);
+		}
+
+		let string = copy_string(string, ARGUMENT_MAX - total, error::numbers::ARGUMENT_LIST_TOO_LONG)?;
+		total += string.len() + 1;
+		strings.push(string);
+	}
+}
\ No newline at end of file
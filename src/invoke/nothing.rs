@@ -1,6 +1,6 @@
 /// Handler for system invoke that should do nothing but succeed
 /// Used for syscalls that are not implemented but should not cause errors
-/// Examples: close(), ioctl(), arch_prctl(), set_tid_address()
+/// Examples: ioctl()
 pub extern "C" fn nothing() -> i32 {
 	0
 }
\ No newline at end of file
@@ -0,0 +1,44 @@
+use {
+	super::copy_path,
+	crate::{
+		error::Errno,
+		scheduler,
+		file::{
+			vfs::{
+				self,
+				descriptor::{self, Descriptor, OpenOptions},
+			},
+		},
+	},
+};
+
+pub extern "C" fn open(path: *const u8, flags: i32, _mode: u32) -> isize {
+	debug!("enter invoke open.");
+
+	let path = match copy_path(path) {
+		Ok(path) => path,
+		Err(error) => return error.to_return(),
+	};
+
+	let flags = OpenOptions::from_bits_truncate(flags);
+
+	vfs::open(&path, flags).map_or_else(
+		|error| error.to_return(),
+		|descriptor| {
+			if flags.contains(OpenOptions::CLOSE_ON_EXEC) {
+				scheduler::get_current_process().borrow_mut().close_on_exec.insert(descriptor);
+			}
+
+			descriptor as isize
+		},
+	)
+}
+
+pub extern "C" fn close(descriptor: Descriptor) -> isize {
+	debug!("enter invoke close.");
+
+	descriptor::close(descriptor).map_or_else(
+		|error| error.to_return(),
+		|_| 0,
+	)
+}
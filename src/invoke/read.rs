@@ -0,0 +1,38 @@
+use {
+	super::TRANSFER_SIZE,
+	crate::{
+		error::Errno,
+		arch::memory::user::{access_ok, copy_to_user},
+		file::{
+			vfs::{
+				descriptor::{
+					self, Descriptor
+				},
+			},
+		},
+	},
+	alloc::vec,
+	core::cmp::min,
+};
+
+pub extern "C" fn read(descriptor: Descriptor, buffer: *mut u8, length: usize) -> isize {
+	debug!("enter invoke read.");
+
+	let mut kernel_buffer = vec![0u8; min(length, TRANSFER_SIZE)];
+
+	// devices like the serial port lose the bytes taken from them, so the
+	// buffer is checked before
+	if let Err(error) = access_ok(buffer as usize, kernel_buffer.len(), true) {
+		return error.to_return();
+	}
+
+	let count = match descriptor::read(descriptor, &mut kernel_buffer) {
+		Ok(count) => count,
+		Err(error) => return error.to_return(),
+	};
+
+	copy_to_user(buffer, &kernel_buffer[..count]).map_or_else(
+		|error| error.to_return(),
+		|_| count.try_into().unwrap(),
+	)
+}
\ No newline at end of file
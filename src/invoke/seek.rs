@@ -0,0 +1,30 @@
+use {
+	crate::{
+		error::{numbers, Errno},
+		file::{
+			vfs::{
+				descriptor::{self, Descriptor, SeekFrom},
+			},
+		},
+	},
+};
+
+const SEEK_SET: i32 = 0;
+const SEEK_CUR: i32 = 1;
+const SEEK_END: i32 = 2;
+
+pub extern "C" fn seek(descriptor: Descriptor, offset: isize, whence: i32) -> isize {
+	debug!("enter invoke lseek.");
+
+	let position = match whence {
+		SEEK_SET if offset >= 0 => SeekFrom::Start(offset as usize),
+		SEEK_CUR => SeekFrom::Current(offset),
+		SEEK_END => SeekFrom::End(offset),
+		_ => return numbers::INVALID_ARGUMENT.to_return(),
+	};
+
+	descriptor::seek(descriptor, position).map_or_else(
+		|error| error.to_return(),
+		|position| position as isize,
+	)
+}
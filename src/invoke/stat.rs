@@ -0,0 +1,147 @@
+use {
+	super::copy_path,
+	crate::{
+		error::{numbers, Errno},
+		arch::memory::user::put_user,
+		file::{
+			vfs::{
+				self,
+				NodeKind,
+				descriptor::{self, Descriptor, State},
+				types::Metadata,
+			},
+		},
+	},
+	alloc::format,
+};
+
+/// Use the current working directory for relative paths in the `*at` invokes
+const AT_FDCWD: Descriptor = -100;
+
+/// Operate on the descriptor itself when the path is empty
+const AT_EMPTY_PATH: i32 = 0x1000;
+
+const S_IFCHR: u32 = 0o020000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+const BLOCK_SIZE: i64 = 4096;
+
+/// File status as returned by stat() on Linux x86-64
+#[repr(C)]
+pub struct FileStatus {
+	pub device: u64,
+	pub inode: u64,
+	pub links: u64,
+	pub mode: u32,
+	pub user_identifier: u32,
+	pub group_identifier: u32,
+	padding: u32,
+	pub special_device: u64,
+	pub size: i64,
+	pub block_size: i64,
+	pub blocks: i64,
+	pub access_time: i64,
+	pub access_time_nanoseconds: i64,
+	pub modified_time: i64,
+	pub modified_time_nanoseconds: i64,
+	pub change_time: i64,
+	pub change_time_nanoseconds: i64,
+	reserved: [i64; 3],
+}
+
+impl FileStatus {
+	fn new(state: State, metadata: Metadata) -> Self {
+		let kind = match metadata.kind {
+			NodeKind::File => S_IFREG,
+			NodeKind::Directory => S_IFDIR,
+			NodeKind::Symlink => S_IFLNK,
+			NodeKind::CharacterDevice => S_IFCHR,
+		};
+
+		FileStatus {
+			device: 0,
+			inode: 0,
+			links: 1,
+			mode: kind | metadata.permission.bits() as u32,
+			user_identifier: metadata.user_identifier,
+			group_identifier: metadata.group_identifier,
+			padding: 0,
+			special_device: 0,
+			size: state.size as i64,
+			block_size: BLOCK_SIZE,
+			blocks: (state.size as i64 + 511) / 512,
+			access_time: metadata.access_time as i64,
+			access_time_nanoseconds: 0,
+			modified_time: metadata.modified_time as i64,
+			modified_time_nanoseconds: 0,
+			change_time: metadata.change_time as i64,
+			change_time_nanoseconds: 0,
+			reserved: [0; 3],
+		}
+	}
+}
+
+pub extern "C" fn file_status(descriptor: Descriptor, status: *mut FileStatus) -> isize {
+	debug!("enter invoke fstat.");
+
+	let result = descriptor::fstat(descriptor).and_then(|state| {
+		descriptor::metadata(descriptor).map(|metadata| FileStatus::new(state, metadata))
+	});
+
+	match result {
+		Ok(result) => put_user(status, result).map_or_else(|error| error.to_return(), |_| 0),
+		Err(error) => error.to_return(),
+	}
+}
+
+/// Returns the error for a path relative to `directory`. Descriptors do not
+/// remember the path they were opened with, so such a path cannot be
+/// resolved even if `directory` is a directory.
+fn relative_to(directory: Descriptor) -> i32 {
+	match descriptor::metadata(directory) {
+		Ok(metadata) if matches!(metadata.kind, NodeKind::Directory) => numbers::NOT_IMPLEMENTED,
+		Ok(_) => numbers::NOT_A_DIRECTORY,
+		Err(error) => error.errno(),
+	}
+}
+
+/// Supports absolute paths and paths relative to AT_FDCWD, which is the
+/// root directory. Other relative paths fail with ENOSYS.
+pub extern "C" fn file_status_at(
+	directory: Descriptor,
+	path: *const u8,
+	status: *mut FileStatus,
+	flags: i32,
+) -> isize {
+	debug!("enter invoke newfstatat.");
+
+	let path = match copy_path(path) {
+		Ok(path) => path,
+		Err(error) => return error.to_return(),
+	};
+
+	if path.is_empty() {
+		if flags & AT_EMPTY_PATH != 0 {
+			return file_status(directory, status);
+		}
+
+		return numbers::FILE_NOT_FOUND.to_return();
+	}
+
+	if !path.starts_with('/') && directory != AT_FDCWD {
+		return relative_to(directory).to_return();
+	}
+
+	let result = vfs::lookup(&format!("/{}", path)).and_then(|file| {
+		let state = file.fstat()?;
+		let metadata = file.metadata()?;
+		Ok(FileStatus::new(state, metadata))
+	});
+
+	match result {
+		Ok(result) => put_user(status, result).map_or_else(|error| error.to_return(), |_| 0),
+		Err(error) => error.to_return(),
+	}
+}
@@ -0,0 +1,53 @@
+use {
+	crate::{
+		arch::{
+			kernel::scheduling::tls::{get_user_fs_base, get_user_gs_base, set_user_fs_base, set_user_gs_base},
+			memory::{r#virtual::TASK_VIRTUAL_MEMORY_END, user::put_user, VirtualAddress},
+		},
+		error::{numbers, Errno},
+		scheduler::{get_current_taskid, set_clear_child_tid},
+	},
+};
+
+const ARCH_SET_GS: i32 = 0x1001;
+const ARCH_SET_FS: i32 = 0x1002;
+const ARCH_GET_FS: i32 = 0x1003;
+const ARCH_GET_GS: i32 = 0x1004;
+
+/// Rejects bases outside of user space, which would fault on the next
+/// write of the base register.
+pub(super) fn check_base(address: usize) -> Result<usize, i32> {
+	if VirtualAddress::from_usize(address) >= TASK_VIRTUAL_MEMORY_END {
+		return Err(numbers::OPERATION_NOT_PERMITTED);
+	}
+
+	Ok(address)
+}
+
+fn control(code: i32, address: usize) -> Result<(), i32> {
+	match code {
+		ARCH_SET_FS => check_base(address).map(set_user_fs_base),
+		ARCH_SET_GS => check_base(address).map(set_user_gs_base),
+		ARCH_GET_FS => put_user(address as *mut usize, get_user_fs_base()),
+		ARCH_GET_GS => put_user(address as *mut usize, get_user_gs_base()),
+		_ => Err(numbers::INVALID_ARGUMENT),
+	}
+}
+
+/// Reads or changes the FS and GS base of the calling thread, which libc
+/// uses to locate its thread local storage.
+pub extern "C" fn arch_process_control(code: i32, address: usize) -> isize {
+	debug!("enter invoke arch_prctl.");
+
+	control(code, address).map_or_else(|error| error.to_return(), |_| 0)
+}
+
+/// Registers the word which is cleared when the calling thread exits and
+/// returns the id of the thread.
+pub extern "C" fn set_thread_id_address(address: usize) -> isize {
+	debug!("enter invoke set_tid_address.");
+
+	set_clear_child_tid(VirtualAddress::from_usize(address));
+
+	get_current_taskid().into() as isize
+}
@@ -0,0 +1,116 @@
+use {
+	crate::{
+		arch::memory::user::{get_user, put_user},
+		error::{numbers, Errno},
+		scheduler,
+		time,
+	},
+	core::time::Duration,
+};
+
+const CLOCK_REALTIME: i32 = 0;
+const CLOCK_MONOTONIC: i32 = 1;
+const CLOCK_PROCESS_CPUTIME_ID: i32 = 2;
+const CLOCK_THREAD_CPUTIME_ID: i32 = 3;
+const CLOCK_MONOTONIC_RAW: i32 = 4;
+const CLOCK_REALTIME_COARSE: i32 = 5;
+const CLOCK_MONOTONIC_COARSE: i32 = 6;
+const CLOCK_BOOTTIME: i32 = 7;
+
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+
+/// Seconds and nanoseconds as used by clock_gettime() on Linux x86-64
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TimeSpec {
+	pub seconds: i64,
+	pub nanoseconds: i64,
+}
+
+impl TimeSpec {
+	fn from_duration(duration: Duration) -> Self {
+		TimeSpec {
+			seconds: duration.as_secs() as i64,
+			nanoseconds: i64::from(duration.subsec_nanos()),
+		}
+	}
+
+	fn to_duration(self) -> Result<Duration, i32> {
+		if self.seconds < 0 || !(0..NANOS_PER_SEC).contains(&self.nanoseconds) {
+			return Err(numbers::INVALID_ARGUMENT);
+		}
+
+		Ok(Duration::new(self.seconds as u64, self.nanoseconds as u32))
+	}
+}
+
+/// Seconds and microseconds as used by gettimeofday() on Linux x86-64
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TimeVal {
+	pub seconds: i64,
+	pub microseconds: i64,
+}
+
+/// Time zone reported by gettimeofday(), always UTC
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TimeZone {
+	pub minutes_west: i32,
+	pub dst_time: i32,
+}
+
+fn clock_time(clock: i32) -> Result<Duration, i32> {
+	match clock {
+		CLOCK_REALTIME | CLOCK_REALTIME_COARSE => Ok(time::realtime()),
+		// the kernel is never suspended, so the boot time equals the
+		// monotonic time
+		CLOCK_MONOTONIC | CLOCK_MONOTONIC_RAW | CLOCK_MONOTONIC_COARSE | CLOCK_BOOTTIME => Ok(time::monotonic()),
+		CLOCK_PROCESS_CPUTIME_ID => Ok(scheduler::get_process_runtime()),
+		CLOCK_THREAD_CPUTIME_ID => Ok(scheduler::get_task_runtime()),
+		_ => Err(numbers::INVALID_ARGUMENT),
+	}
+}
+
+pub extern "C" fn clock_get_time(clock: i32, value: *mut TimeSpec) -> isize {
+	debug!("enter invoke clock_gettime.");
+
+	clock_time(clock)
+		.and_then(|time| put_user(value, TimeSpec::from_duration(time)))
+		.map_or_else(|error| error.to_return(), |_| 0)
+}
+
+pub extern "C" fn get_time_of_day(value: *mut TimeVal, zone: *mut TimeZone) -> isize {
+	debug!("enter invoke gettimeofday.");
+
+	let time = time::realtime();
+	let mut result = Ok(());
+
+	if !value.is_null() {
+		result = put_user(value, TimeVal {
+			seconds: time.as_secs() as i64,
+			microseconds: i64::from(time.subsec_micros()),
+		});
+	}
+
+	if !zone.is_null() {
+		result = result.and_then(|_| put_user(zone, TimeZone { minutes_west: 0, dst_time: 0 }));
+	}
+
+	result.map_or_else(|error| error.to_return(), |_| 0)
+}
+
+/// Suspends the calling thread for at least the requested time. Sleeps are
+/// never interrupted, so the remaining time is not written.
+pub extern "C" fn sleep(request: *const TimeSpec, _remaining: *mut TimeSpec) -> isize {
+	debug!("enter invoke nanosleep.");
+
+	let duration = match get_user(request).and_then(TimeSpec::to_duration) {
+		Ok(duration) => duration,
+		Err(error) => return error.to_return(),
+	};
+
+	scheduler::sleep(duration);
+
+	0
+}
@@ -0,0 +1,54 @@
+use {
+	crate::{
+		arch::memory::user::{copy_to_user, put_user},
+		error::{numbers, Errno},
+		scheduler::{self, process::ProcessId},
+	},
+};
+
+/// Return immediately if no child has exited
+const WAIT_NO_HANG: i32 = 1;
+
+/// Report stopped children, accepted but without effect as tasks never stop
+const WAIT_UNTRACED: i32 = 2;
+
+/// Report continued children, accepted but without effect as tasks never stop
+const WAIT_CONTINUED: i32 = 8;
+
+/// Size of the Linux struct rusage, which is zeroed as usage is not tracked
+const RESOURCE_USAGE_SIZE: usize = 144;
+
+pub extern "C" fn wait(pid: i32, status: *mut i32, options: i32, usage: *mut u8) -> isize {
+	debug!("enter invoke wait.");
+
+	if options & !(WAIT_NO_HANG | WAIT_UNTRACED | WAIT_CONTINUED) != 0 {
+		return numbers::INVALID_ARGUMENT.to_return();
+	}
+
+	// without process groups every pid below one selects any child
+	let pid = if pid > 0 { Some(ProcessId::from(pid as u32)) } else { None };
+	let no_hang = options & WAIT_NO_HANG != 0;
+
+	loop {
+		match scheduler::wait_child(pid, !no_hang) {
+			Ok(Some((id, exit_status))) => {
+				if !status.is_null() {
+					if let Err(error) = put_user(status, exit_status.wait_status()) {
+						return error.to_return();
+					}
+				}
+
+				if !usage.is_null() {
+					if let Err(error) = copy_to_user(usage, &[0u8; RESOURCE_USAGE_SIZE]) {
+						return error.to_return();
+					}
+				}
+
+				return id.into() as isize;
+			}
+			Ok(None) if no_hang => return 0,
+			Ok(None) => scheduler::reschedule(),
+			Err(error) => return error.to_return(),
+		}
+	}
+}
@@ -1,5 +1,8 @@
 use {
+	super::TRANSFER_SIZE,
 	crate::{
+		error::{numbers, Errno},
+		arch::memory::user::{copy_from_user, get_user},
 		file::{
 			vfs::{
 				descriptor::{
@@ -8,33 +11,81 @@ use {
 			},
 		},
 	},
+	alloc::vec,
+	core::cmp::min,
 };
 
+/// Largest number of segments accepted by writev()
+const MAX_SEGMENTS: i32 = 1024;
+
 /// I/O Vector structure for vectored I/O operations
 /// Represents a single buffer in a scatter-gather I/O operation
+#[derive(Clone, Copy)]
 #[repr(C)]  
 pub struct BufferSegment {
 	pub base: *const u8,  
 	pub length: usize,  
 }
 
-pub unsafe extern "C" fn write_vector(
+/// Writes a user buffer to the descriptor in chunks of at most `TRANSFER_SIZE`
+/// bytes and returns the number of bytes written.
+fn write_from_user(descriptor: Descriptor, buffer: *const u8, length: usize) -> Result<usize, i32> {
+	if length == 0 {
+		return descriptor::write(descriptor, &[]).map_err(|error| error.errno());
+	}
+
+	let mut kernel_buffer = vec![0u8; min(length, TRANSFER_SIZE)];
+	let mut written = 0;
+
+	while written < length {
+		let size = min(length - written, TRANSFER_SIZE);
+		let chunk = &mut kernel_buffer[..size];
+
+		if let Err(error) = copy_from_user(chunk, buffer.wrapping_add(written)) {
+			return if written == 0 { Err(error) } else { Ok(written) };
+		}
+
+		match descriptor::write(descriptor, chunk) {
+			Ok(count) => {
+				written += count;
+
+				if count < size {
+					break;
+				}
+			}
+			Err(error) if written == 0 => return Err(error.errno()),
+			Err(_) => break,
+		}
+	}
+
+	Ok(written)
+}
+
+pub extern "C" fn write_vector(
     descriptor: Descriptor,
     pointer: *const BufferSegment,
     count: i32,
 ) -> isize {
 	debug!("enter invoke writev.");
-	let mut length: isize = 0;
 
-	let segment = core::slice::from_raw_parts(pointer, count as usize);
+	if !(0..=MAX_SEGMENTS).contains(&count) {
+		return numbers::INVALID_ARGUMENT.to_return();
+	}
+
+	let mut length: isize = 0;
 
-	for buffer in segment {
-		let slice = core::slice::from_raw_parts(buffer.base, buffer.length);
+	for index in 0..count as usize {
+		let buffer = match get_user(pointer.wrapping_add(index)) {
+			Ok(buffer) => buffer,
+			Err(error) if length == 0 => return error.to_return(),
+			Err(_) => break,
+		};
 
-		let temporary: isize = descriptor::write(descriptor, slice).map_or_else(
-			|error| -(error as isize),
-			|vector| vector.try_into().unwrap(),
-		);
+		let temporary: isize = match write_from_user(descriptor, buffer.base, buffer.length) {
+			Ok(written) => written.try_into().unwrap(),
+			Err(error) if length == 0 => return error.to_return(),
+			Err(_) => break,
+		};
 
 		length += temporary;
 
@@ -46,13 +97,11 @@ pub unsafe extern "C" fn write_vector(
 	length  
 }
 
-pub unsafe extern "C" fn write(descriptor: Descriptor, buffer: *mut u8, length: usize) -> isize {
+pub extern "C" fn write(descriptor: Descriptor, buffer: *const u8, length: usize) -> isize {
 	debug!("enter invoke write.");
 
-	let slice = unsafe { core::slice::from_raw_parts(buffer, length) };
-
-	descriptor::write(descriptor, slice).map_or_else(
-		|error| -(error as isize),
-		|vector| vector.try_into().unwrap(),
+	write_from_user(descriptor, buffer, length).map_or_else(
+		|error| error.to_return(),
+		|written| written.try_into().unwrap(),
 	)
 }
\ No newline at end of file
@@ -3,53 +3,91 @@ use {
 		consts::*,
 	},
 	super::{
-		error::numbers,
-		file::vfs::File,
+		file::vfs::{self, File},
+		scheduler::{self, get_current_process, process::MemoryRegion},
 		arch::{
 			kernel::invoke::transition::to_user_mode,
 			memory::{
-				physical::allocate,
+				user::copy_to_user,
+				physical,
 				paging::{
-					map, BasePageSize, PageSize, PageTableEntryFlags
+					drop_user_space, get_page_table_entry, map, virtual_to_physical, BasePageSize, PageSize, PageTableEntryFlags
 				},
+				r#virtual::{KERNEL_VIRTUAL_MEMORY_END, TASK_VIRTUAL_MEMORY_END},
+				VirtualAddress,
 			},
 		},
 	},
 	alloc::{
+		vec,
 		vec::Vec,
 		string::String,
 	},
+	x86::time::rdtsc,
 	core::{
+		mem::size_of,
+		ops::Range,
 		ptr::write_bytes,
 		slice
 	},
-	goblin::{
-		elf, elf::{
-			program_header::{PT_DYNAMIC, PT_GNU_RELRO, PT_LOAD},
-			Elf,
-		},
-		elf64,
-		elf64::{
-			dynamic::{DT_RELA, DT_RELASZ},
-			reloc::{R_386_GLOB_DAT, R_386_RELATIVE},
+	goblin::elf::{
+		header::{EM_X86_64, ET_DYN, ET_EXEC},
+		program_header::{ProgramHeader, PT_GNU_RELRO, PT_LOAD, PT_PHDR},
+		reloc::{
+			Reloc, R_X86_64_64, R_X86_64_GLOB_DAT, R_X86_64_IRELATIVE, R_X86_64_JUMP_SLOT, R_X86_64_NONE,
+			R_X86_64_RELATIVE,
 		},
+		section_header::{SHN_ABS, SHN_UNDEF},
+		sym::STB_WEAK,
+		Elf,
+	},
+};
+#[cfg(not(target_os = "none"))]
+use goblin::elf::{
+	dynamic::{
+		DF_1_PIE, DT_FLAGS_1, DT_JMPREL, DT_NULL, DT_PLTREL, DT_PLTRELSZ, DT_RELA, DT_RELAENT, DT_RELASZ, DT_STRSZ, DT_STRTAB, DT_SYMENT,
+		DT_SYMTAB,
 	},
+	program_header::PT_DYNAMIC,
 };
 use crate::file::vfs::descriptor;
 
+/// Upper bound for the strings, pointer arrays and auxiliary vector placed
+/// on the initial user stack.
+pub const ARGUMENT_MAX: usize = 0x8000;
+
+const AT_NULL: usize = 0;
+const AT_PHDR: usize = 3;
+const AT_PHENT: usize = 4;
+const AT_PHNUM: usize = 5;
+const AT_PAGESZ: usize = 6;
+const AT_BASE: usize = 7;
+const AT_ENTRY: usize = 9;
+const AT_RANDOM: usize = 25;
+
+/// Entries of the auxiliary vector set up by `process_elf`, without
+/// AT_RANDOM and AT_NULL
+const AUXV_LENGTH: usize = 6;
+
+/// A position independent executable and its heap end below the largest
+/// stack and its guard page.
+const PROGRAM_END: VirtualAddress = VirtualAddress::from_usize(USER_STACK_TOP.as_usize() - USER_STACK_MAX - BasePageSize::SIZE);
+
 #[derive(Debug, PartialEq)]
 pub enum Error {
-	InvalidArgument = numbers::INVALID_ARGUMENT as isize,
-	NoBufferSpace = numbers::NO_BUFFER_SPACE as isize,
-	ValueOverflow = numbers::VALUE_OVERFLOW as isize,
-	FsError,
+	InvalidArgument,
+	NoBufferSpace,
+	ValueOverflow,
+	ArgumentListTooLong,
+	InvalidExecutable,
+	FsError(vfs::Error),
 }
 
 pub fn load_file(path: &String) -> Result<Vec<u8>, Error> {
 	debug!("attempting to load application from path.");
 
-	let file = File::open(path).map_err(|_| Error::FsError)?;
-	let length = file.len().map_err(|_| Error::FsError)?;
+	let file = File::open(path).map_err(Error::FsError)?;
+	let length = file.len().map_err(Error::FsError)?;
 
 	if length == 0 {
 		error!("file is empty.");
@@ -72,8 +110,360 @@ pub fn load_file(path: &String) -> Result<Vec<u8>, Error> {
 	Ok(buffer)
 }
 
-pub fn process_elf(buffer: Vec<u8>) -> Result<(), Error> {
-	let elf = match Elf::parse(&buffer) {
+/// Fills `bytes` with data for AT_RANDOM, derived from the time stamp counter.
+fn random_bytes(bytes: &mut [u8]) {
+	let mut state = unsafe { rdtsc() } | 1;
+
+	for byte in bytes.iter_mut() {
+		state ^= state << 13;
+		state ^= state >> 7;
+		state ^= state << 17;
+		*byte = state as u8;
+	}
+}
+
+/// Returns the size of the initial stack holding `argv`, `envp` and an
+/// auxiliary vector of `auxv_length` entries, which must not exceed
+/// `ARGUMENT_MAX`.
+fn initial_stack_size(argv: &[String], envp: &[String], auxv_length: usize) -> Result<usize, Error> {
+	let strings: usize = argv.iter().chain(envp.iter()).map(|string| string.len() + 1).sum();
+	let words_len = 1 + (argv.len() + 1) + (envp.len() + 1) + 2 * (auxv_length + 2);
+	let size = 16 + strings + words_len * size_of::<usize>();
+
+	if size > ARGUMENT_MAX {
+		error!("argument and environment strings exceed the initial stack.");
+		return Err(Error::ArgumentListTooLong);
+	}
+
+	Ok(size)
+}
+
+/// Builds the System V initial process stack below `USER_STACK_TOP` and
+/// returns the resulting stack pointer. Starting at the stack pointer the
+/// stack holds argc, the null terminated argv and envp arrays and the
+/// auxiliary vector, followed by the strings and random bytes they refer to.
+fn create_initial_stack(argv: &[String], envp: &[String], auxv: &[(usize, usize)]) -> Result<usize, Error> {
+	let mut data: Vec<u8> = vec![0; 16];
+	random_bytes(&mut data);
+
+	initial_stack_size(argv, envp, auxv.len())?;
+
+	let mut offsets: Vec<usize> = Vec::with_capacity(argv.len() + envp.len());
+	for string in argv.iter().chain(envp.iter()) {
+		offsets.push(data.len());
+		data.extend_from_slice(string.as_bytes());
+		data.push(0);
+	}
+
+	let words_len = 1 + (argv.len() + 1) + (envp.len() + 1) + 2 * (auxv.len() + 2);
+
+	let data_start = align_down!(USER_STACK_TOP.as_usize() - data.len(), 16);
+	let stack = align_down!(data_start - words_len * size_of::<usize>(), 16);
+
+	let mut words: Vec<usize> = Vec::with_capacity(words_len);
+	words.push(argv.len());
+	words.extend(offsets[..argv.len()].iter().map(|offset| data_start + offset));
+	words.push(0);
+	words.extend(offsets[argv.len()..].iter().map(|offset| data_start + offset));
+	words.push(0);
+
+	for (key, value) in auxv {
+		words.push(*key);
+		words.push(*value);
+	}
+
+	words.push(AT_RANDOM);
+	words.push(data_start);
+	words.push(AT_NULL);
+	words.push(0);
+
+	let words = unsafe {
+		slice::from_raw_parts(words.as_ptr() as *const u8, words.len() * size_of::<usize>())
+	};
+
+	copy_to_user(data_start as *mut u8, &data).map_err(|_| Error::NoBufferSpace)?;
+	copy_to_user(stack as *mut u8, words).map_err(|_| Error::NoBufferSpace)?;
+
+	debug!("initial user stack at 0x{:x} with {} arguments.", stack, argv.len());
+
+	Ok(stack)
+}
+
+/// Returns the user address of the program header table, either from the
+/// PT_PHDR segment or from the loadable segment containing it.
+fn program_headers_address(elf: &Elf, bias: usize) -> Option<usize> {
+	let phoff = elf.header.e_phoff;
+
+	let vaddr = elf.program_headers
+		.iter()
+		.find(|header| header.p_type == PT_PHDR)
+		.map(|header| header.p_vaddr)
+		.or_else(|| {
+			elf.program_headers
+				.iter()
+				.find(|header| {
+					header.p_type == PT_LOAD && phoff >= header.p_offset && phoff < header.p_offset + header.p_filesz
+				})
+				.map(|header| header.p_vaddr + (phoff - header.p_offset))
+		})?;
+
+	Some(bias + vaddr as usize)
+}
+
+/// Translates the `p_flags` of a loadable segment into page table flags.
+fn segment_flags(header: &ProgramHeader) -> PageTableEntryFlags {
+	let mut flags = PageTableEntryFlags::USER_ACCESSIBLE;
+
+	if header.is_write() {
+		flags |= PageTableEntryFlags::WRITABLE;
+	}
+
+	if !header.is_executable() {
+		flags |= PageTableEntryFlags::EXECUTE_DISABLE;
+	}
+
+	flags
+}
+
+/// Combines the permissions of two segments sharing a page.
+fn merge_flags(first: PageTableEntryFlags, second: PageTableEntryFlags) -> PageTableEntryFlags {
+	let executable = !first.contains(PageTableEntryFlags::EXECUTE_DISABLE) || !second.contains(PageTableEntryFlags::EXECUTE_DISABLE);
+	let mut flags = first | second;
+
+	if executable {
+		flags.remove(PageTableEntryFlags::EXECUTE_DISABLE);
+	}
+
+	flags
+}
+
+/// Returns whether `[address, address + length)` in the linked address space
+/// of `elf` lies completely inside one of its loadable segments.
+fn is_loaded(elf: &Elf, address: usize, length: usize) -> bool {
+	elf.program_headers
+		.iter()
+		.filter(|header| header.p_type == PT_LOAD)
+		.any(|header| {
+			let range = header.vm_range();
+			address >= range.start && address.checked_add(length).is_some_and(|end| end <= range.end)
+		})
+}
+
+/// Returns whether a static executable may be loaded at `range`, which has
+/// to lie in the user space outside of the windows reserved for the stack,
+/// the interpreter and memory mappings.
+///
+/// The kernel image and its virtual memory occupy everything below
+/// `KERNEL_VIRTUAL_MEMORY_END`, so executables linked at the usual 0x400000
+/// are rejected. They have to be linked at or above 0x80_0000_0000, e.g. with
+/// `-Wl,-Ttext-segment=0x10000000000`.
+fn is_user_range(range: &Range<usize>) -> bool {
+	let reserved = [
+		0..KERNEL_VIRTUAL_MEMORY_END.as_usize(),
+		(USER_STACK_TOP - USER_STACK_MAX - BasePageSize::SIZE).as_usize()..USER_STACK_TOP.as_usize(),
+		INTERPRETER_BASE.as_usize()..INTERPRETER_END.as_usize(),
+		MAPPING_START.as_usize()..MAPPING_END.as_usize(),
+	];
+
+	range.end <= TASK_VIRTUAL_MEMORY_END.as_usize()
+		&& reserved.iter().all(|window| range.end <= window.start || range.start >= window.end)
+}
+
+/// Backs `[start, end)` with zeroed and writable user pages. Pages which are
+/// already present, because the previous segment shares them, are kept.
+fn map_segment(start: usize, end: usize) -> Result<(), Error> {
+	for page in (start..end).step_by(BasePageSize::SIZE) {
+		let virtual_address = VirtualAddress::from_usize(page);
+
+		if get_page_table_entry::<BasePageSize>(virtual_address).is_some_and(|entry| entry.is_user()) {
+			continue;
+		}
+
+		let physical_address = physical::allocate_aligned(BasePageSize::SIZE, BasePageSize::SIZE);
+		if physical_address.as_u64() == 0 {
+			error!("failed to allocate physical memory for executable.");
+			return Err(Error::NoBufferSpace);
+		}
+
+		map::<BasePageSize>(
+			virtual_address,
+			physical_address,
+			1,
+			PageTableEntryFlags::WRITABLE | PageTableEntryFlags::USER_ACCESSIBLE | PageTableEntryFlags::EXECUTE_DISABLE,
+		);
+
+		unsafe {
+			write_bytes(virtual_address.as_mut_ptr::<u8>(), 0x00, BasePageSize::SIZE);
+		}
+	}
+
+	Ok(())
+}
+
+/// Returns the relocated value of the dynamic symbol `index`. Undefined weak
+/// symbols resolve to zero, any other undefined symbol is an error since no
+/// shared objects are loaded next to the executable.
+fn resolve_symbol(elf: &Elf, bias: usize, index: usize) -> Result<usize, Error> {
+	let symbol = match elf.dynsyms.get(index) {
+		Some(symbol) => symbol,
+		None => {
+			error!("relocation references invalid symbol {}.", index);
+			return Err(Error::InvalidArgument);
+		}
+	};
+
+	match symbol.st_shndx as u32 {
+		SHN_UNDEF if symbol.st_bind() == STB_WEAK => Ok(0),
+		SHN_UNDEF => {
+			error!("undefined symbol {}.", elf.dynstrtab.get_at(symbol.st_name).unwrap_or("<unknown>"));
+			Err(Error::InvalidArgument)
+		}
+		SHN_ABS => Ok(symbol.st_value as usize),
+		_ => Ok(bias + symbol.st_value as usize),
+	}
+}
+
+/// Returns the address and the new value of the word patched by `reloc` in
+/// an image displaced by `bias` from its linked addresses, or `None` if the
+/// relocation has no effect.
+fn relocation(elf: &Elf, bias: usize, reloc: &Reloc) -> Result<Option<(usize, u64)>, Error> {
+	if reloc.r_offset > usize::MAX as u64 {
+		error!("relocation offset exceeds supported range.");
+		return Err(Error::ValueOverflow);
+	}
+
+	if !is_loaded(elf, reloc.r_offset as usize, size_of::<u64>()) {
+		error!("relocation target is outside allocated memory.");
+		return Err(Error::InvalidArgument);
+	}
+
+	let addend = reloc.r_addend.unwrap_or(0);
+
+	let value = match reloc.r_type {
+		R_X86_64_NONE => return Ok(None),
+		R_X86_64_RELATIVE => (bias as i64).wrapping_add(addend) as u64,
+		R_X86_64_64 => (resolve_symbol(elf, bias, reloc.r_sym)? as i64).wrapping_add(addend) as u64,
+		R_X86_64_GLOB_DAT | R_X86_64_JUMP_SLOT => resolve_symbol(elf, bias, reloc.r_sym)? as u64,
+		// the resolver is user code and must not run in the kernel. The
+		// self-relocation of a static PIE runs it, an executable with a
+		// fixed address does not relocate itself.
+		R_X86_64_IRELATIVE if elf.header.e_type == ET_DYN => return Ok(None),
+		R_X86_64_IRELATIVE => {
+			error!("indirect functions are not resolved in static executables.");
+			return Err(Error::InvalidExecutable);
+		}
+		r_type => {
+			error!("unsupported relocation type {}.", r_type);
+			return Err(Error::InvalidExecutable);
+		}
+	};
+
+	Ok(Some((bias + reloc.r_offset as usize, value)))
+}
+
+/// Processes the dynamic relocations of a loaded image whose segments are
+/// displaced by `bias` from their linked addresses.
+fn relocate(elf: &Elf, bias: usize) -> Result<(), Error> {
+	for reloc in elf.dynrelas.iter().chain(elf.pltrelocs.iter()) {
+		if let Some((address, value)) = relocation(elf, bias, &reloc)? {
+			unsafe {
+				(address as *mut u64).write_unaligned(value);
+			}
+		}
+	}
+
+	Ok(())
+}
+
+/// Applies the final segment permissions once the image is relocated and
+/// registers the segments with the address space of the current process.
+/// A page shared by two segments gets the union of their permissions, the
+/// pages completely covered by `relro` become read-only.
+fn protect_segments(elf: &Elf, bias: usize, relro: Option<Range<usize>>) {
+	let process = get_current_process();
+	let mut process = process.borrow_mut();
+	let address_space = &mut process.address_space;
+	let mut previous: Option<(usize, PageTableEntryFlags)> = None;
+
+	for header in elf.program_headers.iter().filter(|header| header.p_type == PT_LOAD) {
+		let range = header.vm_range();
+		let mut start = align_down!(range.start + bias, BasePageSize::SIZE);
+		let end = align_up!(range.end + bias, BasePageSize::SIZE);
+		let flags = segment_flags(header);
+
+		if let Some((previous_end, previous_flags)) = previous {
+			if start < previous_end {
+				let virtual_address = VirtualAddress::from_usize(start);
+				map::<BasePageSize>(virtual_address, virtual_to_physical(virtual_address), 1, merge_flags(previous_flags, flags));
+				start = previous_end;
+			}
+		}
+
+		for page in (start..end).step_by(BasePageSize::SIZE) {
+			let virtual_address = VirtualAddress::from_usize(page);
+			map::<BasePageSize>(virtual_address, virtual_to_physical(virtual_address), 1, flags);
+		}
+
+		if start < end {
+			address_space.insert_region(MemoryRegion::new(
+				VirtualAddress::from_usize(start),
+				VirtualAddress::from_usize(end),
+				flags,
+			));
+		}
+
+		previous = Some((end, flags));
+	}
+
+	if let Some(relro) = relro {
+		let start = align_down!(relro.start + bias, BasePageSize::SIZE);
+		let end = align_down!(relro.end + bias, BasePageSize::SIZE);
+
+		if start < end {
+			debug!("protect relocation read-only range 0x{:x} - 0x{:x}.", start, end);
+
+			let flags = PageTableEntryFlags::USER_ACCESSIBLE | PageTableEntryFlags::EXECUTE_DISABLE;
+
+			for page in (start..end).step_by(BasePageSize::SIZE) {
+				let virtual_address = VirtualAddress::from_usize(page);
+				map::<BasePageSize>(virtual_address, virtual_to_physical(virtual_address), 1, flags);
+			}
+
+			address_space.protect_region(VirtualAddress::from_usize(start), VirtualAddress::from_usize(end), flags);
+		}
+	}
+}
+
+/// Describes an ELF image mapped into the address space of the current process.
+struct LoadedImage {
+	bias: usize,
+	entry: usize,
+	end: usize,
+	program_headers: usize,
+	program_header_size: usize,
+	program_header_count: usize,
+	interpreter: Option<String>,
+}
+
+/// An ELF image which passed `check_image` and is ready to be mapped
+struct CheckedImage<'a> {
+	elf: Elf<'a>,
+	bias: usize,
+	end: usize,
+	/// Linked range made read-only after relocation, if the kernel
+	/// relocates the image
+	relro: Option<Range<usize>>,
+	/// Whether the kernel relocates the image or leaves it to the
+	/// interpreter
+	relocate: bool,
+}
+
+/// Checks everything `load_image` requires of `buffer` without touching the
+/// address space. Position independent images and interpreters have to fit
+/// inside `[base, limit)`, static executables at their linked addresses
+/// anywhere in the user space.
+fn check_image(buffer: &[u8], base: VirtualAddress, limit: VirtualAddress, is_interpreter: bool) -> Result<CheckedImage<'_>, Error> {
+	let elf = match Elf::parse(buffer) {
 		Ok(parsed) => parsed,
 		Err(_) => {
 			error!("failed to parse elf file format.");
@@ -83,191 +473,493 @@ pub fn process_elf(buffer: Vec<u8>) -> Result<(), Error> {
 
 	debug!("successfully parsed elf file.");
 
-	if elf.is_lib {
+	if elf.is_lib && !is_interpreter {
 		error!("file is a shared library, not an executable.");
 		return Err(Error::InvalidArgument);
 	}
 
-	if !elf.is_64 {
-		error!("file is not a 64-bit executable.");
+	if !elf.is_64 || elf.header.e_machine != EM_X86_64 {
+		error!("file is not a 64-bit x86 executable.");
 		return Err(Error::InvalidArgument);
 	}
 
-	if !elf.libraries.is_empty() {
-		error!("file has library dependencies which are not supported.");
+	if elf.interpreter.is_some() && is_interpreter {
+		error!("interpreter requests another interpreter.");
 		return Err(Error::InvalidArgument);
 	}
 
-	let virtual_start: usize = 0;
-	let mut size: usize = 0;
-	let mut has_loadable = false;
+	if elf.interpreter.is_none() && !elf.libraries.is_empty() {
+		error!("file has library dependencies but no interpreter.");
+		return Err(Error::InvalidArgument);
+	}
 
-	for header in &elf.program_headers {
-		if header.p_type == PT_LOAD {
-			has_loadable = true;
+	let mut virtual_start = usize::MAX;
+	let mut virtual_end: usize = 0;
+	let mut previous_end: usize = 0;
 
-			if header.p_vaddr > usize::MAX as u64 || header.p_memsz > usize::MAX as u64 {
-				error!("program header addresses exceed supported range.");
-				return Err(Error::ValueOverflow);
-			}
+	for header in elf.program_headers.iter().filter(|header| header.p_type == PT_LOAD) {
+		if header.p_vaddr > usize::MAX as u64 || header.p_memsz > usize::MAX as u64 {
+			error!("program header addresses exceed supported range.");
+			return Err(Error::ValueOverflow);
+		}
 
-			let segment_end = header.p_vaddr as usize + header.p_memsz as usize;
-			if segment_end < header.p_vaddr as usize {
+		let segment_end = match (header.p_vaddr as usize).checked_add(header.p_memsz as usize) {
+			Some(end) => end,
+			None => {
 				error!("program header size causes integer overflow.");
 				return Err(Error::ValueOverflow);
 			}
+		};
 
-			size = align_up!(
-                header.p_vaddr as usize - virtual_start + header.p_memsz as usize,
-                BasePageSize::SIZE
-            );
+		if segment_end > TASK_VIRTUAL_MEMORY_END.as_usize() {
+			error!("program header addresses exceed the user address space.");
+			return Err(Error::InvalidArgument);
+		}
+
+		if header.p_filesz > header.p_memsz ||
+			header.p_offset > buffer.len() as u64 ||
+			header.p_filesz > buffer.len() as u64 - header.p_offset {
+			error!("program header references data beyond file boundaries.");
+			return Err(Error::InvalidArgument);
 		}
-	}
 
-	debug!("virtual start address is 0x{:x}.", virtual_start);
-	debug!("required memory size is 0x{:x} bytes.", size);
+		// loadable segments are sorted by address and may only share pages
+		if (header.p_vaddr as usize) < previous_end {
+			error!("loadable segments overlap or are not sorted.");
+			return Err(Error::InvalidArgument);
+		}
 
-	if !has_loadable || size == 0 {
+		previous_end = segment_end;
+		virtual_start = virtual_start.min(align_down!(header.p_vaddr as usize, BasePageSize::SIZE));
+		virtual_end = virtual_end.max(align_up!(segment_end, BasePageSize::SIZE));
+	}
+
+	if virtual_start >= virtual_end {
 		error!("no loadable program segments found in elf file.");
 		return Err(Error::InvalidArgument);
 	}
 
-	let physical_address = allocate(size);
-	if physical_address.as_u64() == 0 {
-		error!("failed to allocate physical memory for executable.");
-		return Err(Error::NoBufferSpace);
+	let bias = match elf.header.e_type {
+		ET_DYN => base.as_usize().wrapping_sub(virtual_start),
+		ET_EXEC => 0,
+		_ => {
+			error!("file is neither a static nor a position independent executable.");
+			return Err(Error::InvalidArgument);
+		}
+	};
+
+	let range = virtual_start.wrapping_add(bias)..virtual_end.wrapping_add(bias);
+	let fits = if elf.header.e_type == ET_EXEC && !is_interpreter {
+		is_user_range(&range)
+	} else {
+		range.start >= base.as_usize() && range.end <= limit.as_usize()
+	};
+
+	if !fits {
+		error!("executable is not linked inside its part of the user address space.");
+		return Err(Error::InvalidArgument);
 	}
 
-	map::<BasePageSize>(
-		USER_ENTRY,
-		physical_address,
-		size / BasePageSize::SIZE,
-		PageTableEntryFlags::WRITABLE | PageTableEntryFlags::USER_ACCESSIBLE,
-	);
+	debug!("virtual address range is 0x{:x} - 0x{:x}.", range.start, range.end);
 
-	unsafe {
-		write_bytes(USER_ENTRY.as_mut_ptr::<u8>(), 0x00, size);
+	if elf.entry > usize::MAX as u64 || !is_loaded(&elf, elf.entry as usize, 1) {
+		error!("entry point is outside loaded executable memory.");
+		return Err(Error::InvalidArgument);
 	}
 
-	let mut base_address: u64 = 0;
-	let mut total_size: u64 = 0;
+	// the interpreter relocates itself and the executable and protects
+	// RELRO afterwards
+	let relocate = !is_interpreter && elf.interpreter.is_none();
 
-	for header in &elf.program_headers {
-		if header.p_type == PT_LOAD {
-			debug!("loading segment at virtual address 0x{:x}.", header.p_vaddr);
+	if relocate {
+		for reloc in elf.dynrelas.iter().chain(elf.pltrelocs.iter()) {
+			relocation(&elf, bias, &reloc)?;
+		}
+	}
 
-			if header.p_offset > buffer.len() as u64 ||
-				header.p_filesz > buffer.len() as u64 ||
-				header.p_offset + header.p_filesz > buffer.len() as u64 {
-				error!("program header references data beyond file boundaries.");
-				return Err(Error::InvalidArgument);
-			}
+	let relro = elf.program_headers
+		.iter()
+		.find(|header| header.p_type == PT_GNU_RELRO && relocate)
+		.map(|header| header.vm_range());
+
+	Ok(CheckedImage {
+		elf,
+		bias,
+		end: range.end,
+		relro,
+		relocate,
+	})
+}
 
-			let memory_offset = header.p_vaddr as usize - virtual_start;
-			if memory_offset >= size {
-				error!("program header virtual address is outside allocated memory.");
-				return Err(Error::InvalidArgument);
-			}
+/// Maps the loadable segments of `buffer` into the current address space
+/// after checking them with `check_image`.
+fn load_image(buffer: &[u8], base: VirtualAddress, limit: VirtualAddress, is_interpreter: bool) -> Result<LoadedImage, Error> {
+	let CheckedImage { elf, bias, end, relro, relocate: relocated } = check_image(buffer, base, limit, is_interpreter)?;
 
-			let memory = (USER_ENTRY.as_usize() + memory_offset) as *mut u8;
+	for header in elf.program_headers.iter().filter(|header| header.p_type == PT_LOAD) {
+		debug!("loading segment at virtual address 0x{:x}.", header.p_vaddr as usize + bias);
 
-			if header.p_filesz > 0 {
-				let mem_slice = unsafe {
-					slice::from_raw_parts_mut(memory, header.p_filesz as usize)
-				};
+		let start = header.p_vaddr as usize + bias;
 
-				let file_start = header.p_offset as usize;
-				let file_end = (header.p_offset + header.p_filesz) as usize;
-				mem_slice.copy_from_slice(&buffer[file_start..file_end]);
-			}
+		map_segment(
+			align_down!(start, BasePageSize::SIZE),
+			align_up!(start + header.p_memsz as usize, BasePageSize::SIZE),
+		)?;
 
-		} else if header.p_type == PT_GNU_RELRO {
-			debug!("found GNU Relocation Read-Only (RELRO) segment at 0x{:x}.", header.p_vaddr);
+		if header.p_filesz > 0 {
+			let mem_slice = unsafe {
+				slice::from_raw_parts_mut(start as *mut u8, header.p_filesz as usize)
+			};
 
-		} else if header.p_type == PT_DYNAMIC {
-			debug!("processing dynamic segment at 0x{:x}.", header.p_vaddr);
+			let file_start = header.p_offset as usize;
+			let file_end = (header.p_offset + header.p_filesz) as usize;
+			mem_slice.copy_from_slice(&buffer[file_start..file_end]);
+		}
+	}
 
-			if header.p_vaddr > usize::MAX as u64 {
-				error!("dynamic segment address exceeds supported range.");
-				return Err(Error::ValueOverflow);
-			}
+	if relocated {
+		relocate(&elf, bias)?;
+	}
 
-			let memory_offset = header.p_vaddr as usize - virtual_start;
-			if memory_offset >= size {
-				error!("dynamic segment is outside allocated memory.");
-				return Err(Error::InvalidArgument);
-			}
+	protect_segments(&elf, bias, relro);
+
+	Ok(LoadedImage {
+		bias,
+		entry: elf.entry as usize + bias,
+		end,
+		program_headers: program_headers_address(&elf, bias).unwrap_or(0),
+		program_header_size: elf.header.e_phentsize as usize,
+		program_header_count: elf.program_headers.len(),
+		interpreter: elf.interpreter.map(String::from),
+	})
+}
 
-			let memory = (USER_ENTRY.as_u64() + memory_offset as u64) as *mut u8;
-			let dynamic_entries = unsafe {
-				elf::dynamic::dyn64::from_raw(0, memory as usize)
-			};
+pub fn process_elf(buffer: Vec<u8>, argv: &[String], envp: &[String]) -> Result<(), Error> {
+	let program = load_image(&buffer, USER_ENTRY, PROGRAM_END, false)?;
 
-			for entry in dynamic_entries {
-				match entry.d_tag {
-					DT_RELA => base_address = USER_ENTRY.as_u64() + entry.d_val,
-					DT_RELASZ => total_size = entry.d_val,
-					_ => {}
-				}
-			}
+	drop(buffer);
+
+	let (entry, interpreter_base) = match &program.interpreter {
+		Some(path) => {
+			debug!("loading interpreter {}.", path);
+
+			let buffer = load_file(path)?;
+			let interpreter = load_image(&buffer, INTERPRETER_BASE, INTERPRETER_END, true)?;
+
+			(interpreter.entry, interpreter.bias)
 		}
+		None => (program.entry, 0),
+	};
+
+	{
+		let process = get_current_process();
+		let mut process = process.borrow_mut();
+		let address_space = &mut process.address_space;
+
+		// the stack is populated on demand up to the stack limit, a fault
+		// in the inaccessible guard page below it is a stack overflow
+		let stack_size = align_up!(address_space.stack_limit.clamp(USER_STACK_SIZE, USER_STACK_MAX), BasePageSize::SIZE);
+		let stack_bottom = USER_STACK_TOP - stack_size;
+
+		address_space.insert_region(MemoryRegion::new(
+			stack_bottom - BasePageSize::SIZE,
+			stack_bottom,
+			PageTableEntryFlags::EXECUTE_DISABLE,
+		));
+		address_space.insert_region(MemoryRegion::new(
+			stack_bottom,
+			USER_STACK_TOP,
+			PageTableEntryFlags::WRITABLE | PageTableEntryFlags::USER_ACCESSIBLE | PageTableEntryFlags::EXECUTE_DISABLE,
+		));
+		address_space.stack_guard = stack_bottom - BasePageSize::SIZE;
+
+		// the heap starts empty right above the executable
+		address_space.heap_start = VirtualAddress::from_usize(program.end);
+		address_space.program_break = address_space.heap_start;
 	}
 
-	if base_address != 0 && total_size > 0 {
-		debug!("processing relocations at 0x{:x}.", base_address);
+	let auxv: [(usize, usize); AUXV_LENGTH] = [
+		(AT_PHDR, program.program_headers),
+		(AT_PHENT, program.program_header_size),
+		(AT_PHNUM, program.program_header_count),
+		(AT_PAGESZ, BasePageSize::SIZE),
+		(AT_BASE, interpreter_base),
+		(AT_ENTRY, program.entry),
+	];
 
-		let relocations = unsafe {
-			elf64::reloc::from_raw_rela(base_address as *const elf64::reloc::Rela, total_size as usize)
-		};
+	let stack = create_initial_stack(argv, envp, &auxv)?;
 
-		for reloc in relocations {
-			if reloc.r_offset > usize::MAX as u64 {
-				error!("relocation offset exceeds supported range.");
-				return Err(Error::ValueOverflow);
-			}
+	debug!("transferring control to user application at 0x{:x}.", entry);
 
-			let offset_addr = USER_ENTRY.as_usize() - virtual_start + reloc.r_offset as usize;
-			if offset_addr + 8 > USER_ENTRY.as_usize() + size {
-				error!("relocation target is outside allocated memory.");
-				return Err(Error::InvalidArgument);
-			}
+	unsafe {
+		to_user_mode(entry, stack);
+	}
+}
 
-			let offset = offset_addr as *mut u64;
-			let reloc_type = reloc.r_info & 0xF;
-
-			match reloc_type {
-				r if r == R_386_RELATIVE as u64 => {
-					unsafe {
-						*offset = (USER_ENTRY.as_usize() as i64 - virtual_start as i64 + reloc.r_addend) as u64;
-					}
-				}
-				r if r == R_386_GLOB_DAT as u64 => {
-					debug!("skipping global data relocation.");
-				}
-				_ => {
-					error!("unsupported relocation type {}.", reloc_type);
-					return Err(Error::InvalidArgument);
-				}
-			}
-		}
+/// Reports every problem of an executable or its interpreter as an invalid
+/// executable, except for a file which cannot be read.
+fn not_executable(error: Error) -> Error {
+	match error {
+		Error::FsError(_) => error,
+		_ => Error::InvalidExecutable,
 	}
+}
 
-	if elf.entry > usize::MAX as u64 {
-		error!("entry point address exceeds supported range.");
-		return Err(Error::ValueOverflow);
+/// Replaces the image of the current process by the executable at `path`.
+///
+/// The executable, its interpreter and the initial stack are checked before
+/// the other threads are terminated and the old image is released, so that
+/// errors are returned to the caller. A failure to load the new image
+/// afterwards terminates the process.
+pub fn execute(path: &String, argv: &[String], envp: &[String]) -> Result<(), Error> {
+	let buffer = load_file(path)?;
+	let program = check_image(&buffer, USER_ENTRY, PROGRAM_END, false).map_err(not_executable)?;
+
+	if let Some(interpreter) = program.elf.interpreter {
+		let buffer = load_file(&String::from(interpreter))?;
+		check_image(&buffer, INTERPRETER_BASE, INTERPRETER_END, true).map_err(not_executable)?;
 	}
 
-	let entry = elf.entry as usize - virtual_start + USER_ENTRY.as_usize();
-	if entry < USER_ENTRY.as_usize() || entry >= USER_ENTRY.as_usize() + size {
-		error!("entry point is outside loaded executable memory.");
-		return Err(Error::InvalidArgument);
+	initial_stack_size(argv, envp, AUXV_LENGTH)?;
+
+	// the other threads would keep running on the released image
+	scheduler::terminate_other_threads();
+
+	{
+		let process = get_current_process();
+		let mut process = process.borrow_mut();
+
+		process.close_descriptors_on_exec();
+		process.address_space.clear();
 	}
 
-	drop(buffer);
+	scheduler::set_clear_child_tid(VirtualAddress::zero());
+	scheduler::reset_fpu_state();
 
-	debug!("transferring control to user application at 0x{:x}.", entry);
+	drop_user_space();
 
-	unsafe {
-		to_user_mode(entry);
+	if let Err(error) = process_elf(buffer, argv, envp) {
+		error!("unable to start {}: {:?}.", path, error);
 	}
+
+	scheduler::abort();
+}
+
+/// Builds a position independent ELF64 image with one writable segment
+/// covering the whole file, a dynamic section and the given `relocations`
+/// and `plt_relocations` as (offset, symbol, type, addend). The dynamic
+/// symbols are 1 `defined` at 0x1100, 2 `absolute` at 0x1234, 3 an
+/// undefined `missing` and 4 an undefined weak `optional`.
+#[cfg(not(target_os = "none"))]
+fn test_image(relocations: &[(u64, u64, u32, i64)], plt_relocations: &[(u64, u64, u32, i64)]) -> Vec<u8> {
+	const SIZE: usize = 0x2000;
+	const DYNAMIC: usize = 0x200;
+	const STRTAB: usize = 0x300;
+	const SYMTAB: usize = 0x340;
+	const RELA: usize = 0x400;
+	const JMPREL: usize = 0x600;
+
+	fn put(image: &mut [u8], offset: usize, bytes: &[u8]) {
+		image[offset..offset + bytes.len()].copy_from_slice(bytes);
+	}
+
+	fn put_relocations(image: &mut [u8], offset: usize, relocations: &[(u64, u64, u32, i64)]) {
+		for (i, (r_offset, symbol, r_type, addend)) in relocations.iter().enumerate() {
+			let entry = offset + i * 24;
+			put(image, entry, &r_offset.to_le_bytes());
+			put(image, entry + 8, &((symbol << 32) | u64::from(*r_type)).to_le_bytes());
+			put(image, entry + 16, &addend.to_le_bytes());
+		}
+	}
+
+	let mut image = vec![0u8; SIZE];
+
+	// file header
+	put(&mut image, 0, &[0x7f, b'E', b'L', b'F', 2, 1, 1]);
+	put(&mut image, 16, &ET_DYN.to_le_bytes());
+	put(&mut image, 18, &EM_X86_64.to_le_bytes());
+	put(&mut image, 20, &1u32.to_le_bytes());
+	put(&mut image, 24, &0x1000u64.to_le_bytes());
+	put(&mut image, 32, &64u64.to_le_bytes());
+	put(&mut image, 52, &64u16.to_le_bytes());
+	put(&mut image, 54, &56u16.to_le_bytes());
+	put(&mut image, 56, &2u16.to_le_bytes());
+	put(&mut image, 58, &64u16.to_le_bytes());
+
+	// PT_LOAD and PT_DYNAMIC
+	let dynamic_size = 12 * 16;
+	for (i, (p_type, offset, size)) in [(PT_LOAD, 0, SIZE), (PT_DYNAMIC, DYNAMIC, dynamic_size)].iter().enumerate() {
+		let header = 64 + i * 56;
+		put(&mut image, header, &p_type.to_le_bytes());
+		put(&mut image, header + 4, &6u32.to_le_bytes());
+		put(&mut image, header + 8, &(*offset as u64).to_le_bytes());
+		put(&mut image, header + 16, &(*offset as u64).to_le_bytes());
+		put(&mut image, header + 24, &(*offset as u64).to_le_bytes());
+		put(&mut image, header + 32, &(*size as u64).to_le_bytes());
+		put(&mut image, header + 40, &(*size as u64).to_le_bytes());
+		put(&mut image, header + 48, &0x1000u64.to_le_bytes());
+	}
+
+	let strings = b"\0defined\0absolute\0missing\0optional\0";
+	put(&mut image, STRTAB, strings);
+
+	// name, info, section and value of the symbols after the null symbol
+	let symbols: [(u32, u8, u16, u64); 4] = [
+		(1, 0x12, 1, 0x1100),
+		(9, 0x11, SHN_ABS as u16, 0x1234),
+		(18, 0x10, SHN_UNDEF as u16, 0),
+		(26, 0x20, SHN_UNDEF as u16, 0),
+	];
+	for (i, (name, info, section, value)) in symbols.iter().enumerate() {
+		let symbol = SYMTAB + (i + 1) * 24;
+		put(&mut image, symbol, &name.to_le_bytes());
+		image[symbol + 4] = *info;
+		put(&mut image, symbol + 6, &section.to_le_bytes());
+		put(&mut image, symbol + 8, &value.to_le_bytes());
+	}
+
+	put_relocations(&mut image, RELA, relocations);
+	put_relocations(&mut image, JMPREL, plt_relocations);
+
+	let dynamic: [(u64, u64); 12] = [
+		(DT_STRTAB, STRTAB as u64),
+		(DT_STRSZ, strings.len() as u64),
+		(DT_SYMTAB, SYMTAB as u64),
+		(DT_SYMENT, 24),
+		(DT_RELA, RELA as u64),
+		(DT_RELASZ, relocations.len() as u64 * 24),
+		(DT_RELAENT, 24),
+		(DT_JMPREL, JMPREL as u64),
+		(DT_PLTRELSZ, plt_relocations.len() as u64 * 24),
+		(DT_PLTREL, DT_RELA),
+		(DT_FLAGS_1, DF_1_PIE),
+		(DT_NULL, 0),
+	];
+	for (i, (tag, value)) in dynamic.iter().enumerate() {
+		put(&mut image, DYNAMIC + i * 16, &tag.to_le_bytes());
+		put(&mut image, DYNAMIC + i * 16 + 8, &value.to_le_bytes());
+	}
+
+	image
+}
+
+/// Returns the effect of every relocation of `image` loaded at `bias`.
+#[cfg(not(target_os = "none"))]
+fn test_relocations(image: &[u8], bias: usize) -> Vec<Result<Option<(usize, u64)>, Error>> {
+	let elf = Elf::parse(image).unwrap();
+
+	elf.dynrelas
+		.iter()
+		.chain(elf.pltrelocs.iter())
+		.map(|reloc| relocation(&elf, bias, &reloc))
+		.collect()
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn relocate_relative() {
+	let image = test_image(&[(0x1800, 0, R_X86_64_RELATIVE, 0x1100), (0x1808, 0, R_X86_64_NONE, 0)], &[]);
+
+	assert_eq!(test_relocations(&image, 0x10000), [Ok(Some((0x11800, 0x11100))), Ok(None)]);
+	assert_eq!(test_relocations(&image, 0), [Ok(Some((0x1800, 0x1100))), Ok(None)]);
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn relocate_symbol() {
+	let image = test_image(&[(0x1800, 1, R_X86_64_64, 8), (0x1808, 2, R_X86_64_64, -4)], &[]);
+
+	assert_eq!(test_relocations(&image, 0x10000), [Ok(Some((0x11800, 0x11108))), Ok(Some((0x11808, 0x1230)))]);
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn relocate_global_data() {
+	let image = test_image(
+		&[(0x1800, 1, R_X86_64_GLOB_DAT, 8), (0x1808, 4, R_X86_64_GLOB_DAT, 0)],
+		&[(0x1810, 1, R_X86_64_JUMP_SLOT, 0), (0x1818, 2, R_X86_64_JUMP_SLOT, 0)],
+	);
+
+	assert_eq!(test_relocations(&image, 0x10000), [
+		Ok(Some((0x11800, 0x11100))),
+		Ok(Some((0x11808, 0))),
+		Ok(Some((0x11810, 0x11100))),
+		Ok(Some((0x11818, 0x1234))),
+	]);
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn relocate_undefined_symbol() {
+	let image = test_image(&[], &[(0x1800, 3, R_X86_64_JUMP_SLOT, 0)]);
+
+	assert_eq!(test_relocations(&image, 0x10000), [Err(Error::InvalidArgument)]);
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn relocate_outside_image() {
+	let image = test_image(&[(0x1ffc, 0, R_X86_64_RELATIVE, 0), (0x3000, 0, R_X86_64_RELATIVE, 0)], &[]);
+
+	assert_eq!(test_relocations(&image, 0x10000), [Err(Error::InvalidArgument), Err(Error::InvalidArgument)]);
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn relocate_unsupported_type() {
+	// R_X86_64_COPY needs the symbol of a shared object
+	let image = test_image(&[(0x1800, 1, 5, 0)], &[]);
+
+	assert_eq!(test_relocations(&image, 0x10000), [Err(Error::InvalidExecutable)]);
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn relocate_indirect_function() {
+	// a static PIE runs the resolver during its self-relocation
+	let mut image = test_image(&[(0x1808, 0, R_X86_64_IRELATIVE, 0x1100)], &[]);
+	assert_eq!(test_relocations(&image, 0x10000), [Ok(None)]);
+
+	// nothing resolves it in an executable at a fixed address
+	image[16..18].copy_from_slice(&ET_EXEC.to_le_bytes());
+	assert_eq!(test_relocations(&image, 0), [Err(Error::InvalidExecutable)]);
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn check_before_loading() {
+	let image = test_image(&[(0x1800, 0, R_X86_64_RELATIVE, 0x1100)], &[]);
+	let checked = check_image(&image, USER_ENTRY, PROGRAM_END, false).unwrap();
+	assert_eq!(checked.bias, USER_ENTRY.as_usize());
+	assert_eq!(checked.end, USER_ENTRY.as_usize() + 0x2000);
+
+	// too large for the window
+	let limit = USER_ENTRY + 0x1000usize;
+	assert_eq!(check_image(&image, USER_ENTRY, limit, false).err(), Some(Error::InvalidArgument));
+
+	// the relocations are checked as well
+	let image = test_image(&[(0x1800, 1, 5, 0)], &[]);
+	assert_eq!(check_image(&image, USER_ENTRY, PROGRAM_END, false).err(), Some(Error::InvalidExecutable));
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn static_link_addresses() {
+	let at = |start: usize| start..start + 0x10000;
+
+	// the kernel image and its virtual memory
+	assert!(!is_user_range(&at(0x400000)));
+	assert!(!is_user_range(&at(0x8000_0000)));
+	assert!(!is_user_range(&at(0x7f_ffff_8000)));
+
+	assert!(is_user_range(&at(0x80_0000_0000)));
+	assert!(is_user_range(&at(0x100_0000_0000)));
+	assert!(is_user_range(&at(USER_ENTRY.as_usize())));
+	assert!(is_user_range(&at(MAPPING_END.as_usize())));
+
+	// the stack, the interpreter, the mappings and the kernel half
+	assert!(!is_user_range(&at(USER_STACK_TOP.as_usize() - 0x8000)));
+	assert!(!is_user_range(&at(INTERPRETER_BASE.as_usize())));
+	assert!(!is_user_range(&at(MAPPING_START.as_usize())));
+	assert!(!is_user_range(&at(TASK_VIRTUAL_MEMORY_END.as_usize() - 0x8000)));
 }
@@ -10,6 +10,8 @@
 #![no_std]
 
 extern crate alloc;
+#[cfg(test)]
+extern crate std;
 #[cfg(target_arch = "x86_64")]
 extern crate x86;
 #[macro_use]
@@ -37,6 +39,7 @@ pub mod io;
 pub mod memory;
 pub mod scheduler;
 pub mod sync;
+pub mod time;
 pub mod invoke;
 pub mod format;
 
@@ -51,7 +54,7 @@ impl Arena {
 
 static mut ARENA: Arena = Arena::new();
 
-#[global_allocator]
+#[cfg_attr(not(test), global_allocator)]
 static ALLOCATOR: LockedHeap<32> = LockedHeap::<32>::new();
 
 pub fn initialize() {
@@ -60,6 +63,7 @@ pub fn initialize() {
 	}
 
 	arch::kernel::initialize();
+	time::initialize();
 	memory::initialize();
 	scheduler::initialize();
 	file::initialize();
@@ -12,7 +12,10 @@ use {
 		io::{load_file, process_elf},
 		scheduler::{self, task::NORMAL_PRIORITY},
 		arch::{
-			kernel::interrupts::interrupt_enable,
+			kernel::{
+				interrupts::interrupt_enable,
+				processor::cpu_halt,
+			},
 		},
 	},
 };
@@ -24,8 +27,10 @@ extern "C" fn create_user() {
 	info!("started application loader.");
 
 	let buffer = load_file(&path).unwrap();
-	
-	_ = process_elf(buffer);
+	let argv = [path.clone()];
+	let envp = [String::from("PATH=/bin")];
+
+	_ = process_elf(buffer, &argv, &envp);
 }
 
 #[cfg(not(test))]
@@ -41,12 +46,16 @@ pub extern "C" fn main() -> i32 {
 		scheduler::spawn(task_test, NORMAL_PRIORITY).unwrap();
 	}
 
-	scheduler::spawn(create_user, NORMAL_PRIORITY).unwrap();
+	scheduler::spawn_process(create_user, NORMAL_PRIORITY).unwrap();
 
 	interrupt_enable();
 
 	scheduler::reschedule();
 
+	while scheduler::has_pending_tasks() {
+		cpu_halt();
+	}
+
 	info!("shutdown system.");
 
 	0
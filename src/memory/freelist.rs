@@ -131,15 +131,15 @@ impl<
 #[test]
 fn add_element() {
 	let mut freelist = FreeList::new();
-	let entry = FreeListEntry::new(0x10000, 0x100000);
+	let entry = FreeListEntry::new(PhysicalAddress(0x10000), PhysicalAddress(0x100000));
 
 	freelist.list.push_back(entry);
 
 	let mut cursor = freelist.list.cursor_front_mut();
 
 	while let Some(node) = cursor.peek_next() {
-		assert!(node.start != 0x1000);
-		assert!(node.end != 0x10000);
+		assert!(node.start != PhysicalAddress(0x1000));
+		assert!(node.end != PhysicalAddress(0x10000));
 
 		cursor.move_next();
 	}
@@ -149,32 +149,34 @@ fn add_element() {
 #[test]
 fn allocate() {
 	let mut freelist = FreeList::new();
-	let entry = FreeListEntry::new(0x10000, 0x100000);
+	let entry = FreeListEntry::new(PhysicalAddress(0x10000), PhysicalAddress(0x100000));
 
 	freelist.list.push_back(entry);
 	let addr = freelist.allocate(0x1000, None);
 
-	assert_eq!(addr.unwrap(), 0x10000);
+	assert_eq!(addr.unwrap(), PhysicalAddress(0x10000));
 
 	let mut cursor = freelist.list.cursor_front_mut();
 	while let Some(node) = cursor.current() {
-		assert_eq!(node.start, 0x11000);
-		assert_eq!(node.end, 0x100000);
+		assert_eq!(node.start, PhysicalAddress(0x11000));
+		assert_eq!(node.end, PhysicalAddress(0x100000));
 
 		cursor.move_next();
 	}
 
 	let addr = freelist.allocate(0x1000, Some(0x2000));
+	assert_eq!(addr.unwrap(), PhysicalAddress(0x12000));
+
 	let mut cursor = freelist.list.cursor_front_mut();
 	assert!(cursor.current().is_some());
 	if let Some(node) = cursor.current() {
-		assert_eq!(node.start, 0x11000);
+		assert_eq!(node.start, PhysicalAddress(0x11000));
 	}
 
 	cursor.move_next();
 	assert!(cursor.current().is_some());
 	if let Some(node) = cursor.current() {
-		assert_eq!(node.start, 0x13000);
+		assert_eq!(node.start, PhysicalAddress(0x13000));
 	}
 }
 
@@ -182,7 +184,7 @@ fn allocate() {
 #[test]
 fn deallocate() {
 	let mut freelist = FreeList::new();
-	let entry = FreeListEntry::new(0x10000, 0x100000);
+	let entry = FreeListEntry::new(PhysicalAddress(0x10000), PhysicalAddress(0x100000));
 
 	freelist.list.push_back(entry);
 	let addr = freelist.allocate(0x1000, None);
@@ -190,8 +192,8 @@ fn deallocate() {
 
 	let mut cursor = freelist.list.cursor_front_mut();
 	while let Some(node) = cursor.current() {
-		assert_eq!(node.start, 0x10000);
-		assert_eq!(node.end, 0x100000);
+		assert_eq!(node.start, PhysicalAddress(0x10000));
+		assert_eq!(node.end, PhysicalAddress(0x100000));
 
 		cursor.move_next();
 	}
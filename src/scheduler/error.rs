@@ -9,6 +9,7 @@ pub enum Error {
     ValueOverflow,
     BadFileDescriptor,
     FileNotFound,
+    NoChildProcess,
 }
 
 impl Debug for Error {
@@ -18,6 +19,7 @@ impl Debug for Error {
             Error::ValueOverflow => f.write_str("ValueOverflow"),
             Error::BadFileDescriptor => f.write_str("BadFileDescriptor"),
             Error::FileNotFound => f.write_str("FileNotFound"),
+            Error::NoChildProcess => f.write_str("NoChildProcess"),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,65 @@
+use {
+	crate::{
+		scheduler::{self, task::TaskId},
+		sync::lock::WaitLockIrqSave,
+	},
+	alloc::sync::Arc,
+};
+
+/// Result of a task spawned with `spawn_with`, stored before it finishes
+pub struct Packet<T> {
+	result: WaitLockIrqSave<Option<T>>,
+}
+
+impl<T> Packet<T> {
+	pub const fn new() -> Self {
+		Packet {
+			result: WaitLockIrqSave::new(None),
+		}
+	}
+
+	pub fn set(&self, value: T) {
+		*self.result.lock() = Some(value);
+	}
+
+	fn take(&self) -> Option<T> {
+		self.result.lock().take()
+	}
+}
+
+impl<T> Default for Packet<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Owned permission to wait for a task spawned with `spawn_with`. Dropping
+/// the handle detaches the task.
+pub struct JoinHandle<T> {
+	id: TaskId,
+	packet: Arc<Packet<T>>,
+}
+
+impl<T> JoinHandle<T> {
+	pub fn new(id: TaskId, packet: Arc<Packet<T>>) -> Self {
+		JoinHandle { id, packet }
+	}
+
+	pub fn id(&self) -> TaskId {
+		self.id
+	}
+
+	/// Returns whether the task has returned its result.
+	pub fn is_finished(&self) -> bool {
+		self.packet.result.lock().is_some()
+	}
+
+	/// Blocks the current task until the task finishes and returns its result.
+	pub fn join(self) -> T {
+		while !scheduler::join(self.id) {
+			scheduler::reschedule();
+		}
+
+		self.packet.take().expect("task finished without a result")
+	}
+}
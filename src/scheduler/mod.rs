@@ -1,11 +1,20 @@
 mod scheduler;
 pub mod task;
-mod error;
+pub mod process;
+pub mod error;
+pub mod join;
+pub mod policy;
+pub mod timer;
 
 use {
 	crate::{
 		scheduler::error::Error,
 		scheduler::task::{Task, TaskPriority},
+		scheduler::process::{ExitStatus, Process, ProcessId},
+		scheduler::join::{JoinHandle, Packet},
+		scheduler::policy::Policy,
+		scheduler::timer::{TimerAction, TimerId},
+		time,
 		file::{
 			vfs::{
 				descriptor::{Descriptor, Interface},
@@ -19,8 +28,9 @@ use {
 			},
 		},
 	},
-	core::cell::RefCell,
+	core::{cell::RefCell, time::Duration},
 	alloc::{
+		boxed::Box,
 		rc::Rc,
 		sync::Arc,
 	},
@@ -53,6 +63,151 @@ pub fn spawn(func: extern "C" fn(), priority: TaskPriority) -> Result<task::Task
 	unsafe { SCHEDULER.as_mut().unwrap().spawn(func, priority) }
 }
 
+fn take_entry() -> Option<Box<dyn FnOnce()>> {
+	unsafe { SCHEDULER.as_mut().unwrap().take_entry() }
+}
+
+/// Spawns a kernel task named `name` which runs `func`. The returned handle
+/// waits for the task and yields the result of `func`.
+pub fn spawn_with<F, T>(name: &str, priority: TaskPriority, func: F) -> Result<JoinHandle<T>, Error>
+where
+	F: FnOnce() -> T + Send + 'static,
+	T: Send + 'static,
+{
+	let packet = Arc::new(Packet::new());
+	let result = packet.clone();
+	let entry = Box::new(move || result.set(func()));
+
+	let id = unsafe { SCHEDULER.as_mut().unwrap().spawn_with(name, entry, priority)? };
+
+	Ok(JoinHandle::new(id, packet))
+}
+
+/// Returns `true` if the task `id` has finished, otherwise queues the
+/// current task until it finishes.
+pub fn join(id: task::TaskId) -> bool {
+	unsafe { SCHEDULER.as_mut().unwrap().join(id) }
+}
+
+pub fn spawn_process(func: extern "C" fn(), priority: TaskPriority) -> Result<ProcessId, Error> {
+	unsafe { SCHEDULER.as_mut().unwrap().spawn_process(func, priority) }
+}
+
+pub fn fork(user_stack_pointer: VirtualAddress) -> Result<ProcessId, Error> {
+	unsafe { SCHEDULER.as_mut().unwrap().fork(user_stack_pointer) }
+}
+
+pub fn spawn_thread(user_stack_pointer: VirtualAddress, thread_pointer: Option<usize>, clear_child_tid: VirtualAddress) -> Result<task::TaskId, Error> {
+	unsafe { SCHEDULER.as_mut().unwrap().spawn_thread(user_stack_pointer, thread_pointer, clear_child_tid) }
+}
+
+/// Blocks the current task for at least `duration`. A zero duration only
+/// gives up the processor.
+pub fn sleep(duration: Duration) {
+	if duration.is_zero() {
+		reschedule();
+		return;
+	}
+
+	// the current tick has already partly passed
+	let deadline = time::ticks().saturating_add(time::duration_to_ticks(duration)).saturating_add(1);
+
+	unsafe { SCHEDULER.as_mut().unwrap().sleep_until_tick(deadline) }
+}
+
+/// Blocks the current task until the monotonic clock reaches `deadline`.
+pub fn sleep_until(deadline: Duration) {
+	sleep(deadline.saturating_sub(time::monotonic()))
+}
+
+/// Runs `callback` once after `delay` in the timer interrupt. The callback
+/// must not block.
+pub fn add_timer(delay: Duration, callback: impl FnMut() + 'static) -> TimerId {
+	let deadline = time::ticks().saturating_add(time::duration_to_ticks(delay)).saturating_add(1);
+
+	unsafe { SCHEDULER.as_mut().unwrap().add_timer(deadline, 0, Box::new(callback)) }
+}
+
+/// Runs `callback` every `period` in the timer interrupt until the timer
+/// is cancelled. The callback must not block.
+pub fn add_periodic_timer(period: Duration, callback: impl FnMut() + 'static) -> TimerId {
+	let period = time::duration_to_ticks(period).max(1);
+
+	unsafe { SCHEDULER.as_mut().unwrap().add_timer(time::ticks().saturating_add(period), period, Box::new(callback)) }
+}
+
+/// Stops the timer `id`. Returns `false` if it has already expired.
+pub fn cancel_timer(id: TimerId) -> bool {
+	unsafe { SCHEDULER.as_mut().unwrap().cancel_timer(id) }
+}
+
+/// Accounts a timer interrupt and preempts the current task once its time
+/// slice is over.
+pub fn timer_tick() {
+	unsafe { SCHEDULER.as_mut().unwrap().timer_tick() }
+}
+
+/// Sets how long a task runs before others of equal priority or, with the
+/// fair policy, any ready task take over.
+pub fn set_time_slice(slice: Duration) {
+	unsafe { SCHEDULER.as_mut().unwrap().set_time_slice(time::duration_to_ticks(slice)) }
+}
+
+pub fn get_policy() -> Policy {
+	unsafe { SCHEDULER.as_ref().unwrap().get_policy() }
+}
+
+/// Selects the scheduling policy, usually once at boot.
+pub fn set_policy(policy: Policy) {
+	unsafe { SCHEDULER.as_mut().unwrap().set_policy(policy) }
+}
+
+/// Returns the processor time used by the current task.
+pub fn get_task_runtime() -> Duration {
+	unsafe { SCHEDULER.as_ref().unwrap().get_runtime(false) }
+}
+
+/// Returns the processor time used by all threads of the current process.
+pub fn get_process_runtime() -> Duration {
+	unsafe { SCHEDULER.as_ref().unwrap().get_runtime(true) }
+}
+
+/// Wakes the sleeping tasks and runs the timers whose deadline is `now` or
+/// earlier. Each callback runs without a borrow of the scheduler.
+pub fn expire_timers(now: u64) {
+	let expired = unsafe { SCHEDULER.as_mut().unwrap().expire_timers(now) };
+
+	for mut timer in expired {
+		if let TimerAction::Callback(callback) = &mut timer.action {
+			callback();
+		}
+
+		if timer.period != 0 {
+			unsafe { SCHEDULER.as_mut().unwrap().rearm_timer(timer) }
+		}
+	}
+}
+
+pub fn restore_fpu_state() {
+	unsafe { SCHEDULER.as_mut().unwrap().restore_fpu_state() }
+}
+
+pub fn reset_fpu_state() {
+	unsafe { SCHEDULER.as_mut().unwrap().reset_fpu_state() }
+}
+
+pub fn futex_wait(key: PhysicalAddress) {
+	unsafe { SCHEDULER.as_mut().unwrap().futex_wait(key) }
+}
+
+pub fn futex_wake(key: PhysicalAddress, count: usize) -> usize {
+	unsafe { SCHEDULER.as_mut().unwrap().futex_wake(key, count) }
+}
+
+pub fn wait_child(pid: Option<ProcessId>, block: bool) -> Result<Option<(ProcessId, ExitStatus)>, Error> {
+	unsafe { SCHEDULER.as_mut().unwrap().wait_child(pid, block) }
+}
+
 pub fn reschedule() {
 	unsafe { SCHEDULER.as_mut().unwrap().reschedule() }
 }
@@ -61,9 +216,20 @@ pub fn schedule() {
 	unsafe { SCHEDULER.as_mut().unwrap().schedule() }
 }
 
-pub fn exit() -> ! {
+pub fn exit(code: i32) -> ! {
+	unsafe {
+		SCHEDULER.as_mut().unwrap().exit(code);
+	}
+}
+
+/// Terminates all threads of the current process except the current task.
+pub fn terminate_other_threads() {
+	unsafe { SCHEDULER.as_mut().unwrap().terminate_other_threads() }
+}
+
+pub fn exit_group(code: i32) -> ! {
 	unsafe {
-		SCHEDULER.as_mut().unwrap().exit();
+		SCHEDULER.as_mut().unwrap().exit_group(code);
 	}
 }
 
@@ -75,6 +241,14 @@ pub fn get_current_interrupt_stack() -> VirtualAddress {
 	unsafe { SCHEDULER.as_mut().unwrap().get_current_interrupt_stack() }
 }
 
+pub fn get_current_task() -> Rc<RefCell<Task>> {
+	unsafe { SCHEDULER.as_ref().unwrap().get_current_task() }
+}
+
+pub fn get_current_process() -> Rc<RefCell<Process>> {
+	unsafe { SCHEDULER.as_ref().unwrap().get_current_process() }
+}
+
 pub fn get_root_page_table() -> PhysicalAddress {
 	unsafe { SCHEDULER.as_mut().unwrap().get_root_page_table() }
 }
@@ -93,6 +267,10 @@ pub fn wakeup_task(task: Rc<RefCell<Task>>) {
 	unsafe { SCHEDULER.as_mut().unwrap().wakeup_task(task) }
 }
 
+pub fn change_priority(task: &Rc<RefCell<Task>>, priority: TaskPriority) {
+	unsafe { SCHEDULER.as_mut().unwrap().change_priority(task, priority) }
+}
+
 pub fn get_io_interface(fd: Descriptor) -> Result<Arc<dyn Interface>, Error> {
 	let _preemption = DisabledPreemption::new();
 
@@ -111,10 +289,18 @@ pub fn remove_io_interface(fd: Descriptor) -> Result<Arc<dyn Interface>, Error>
 	unsafe { SCHEDULER.as_mut().unwrap().remove_io_interface(fd) }
 }
 
+pub fn has_pending_tasks() -> bool {
+	unsafe { SCHEDULER.as_ref().unwrap().has_pending_tasks() }
+}
+
 pub fn get_current_taskid() -> task::TaskId {
 	unsafe { SCHEDULER.as_ref().unwrap().get_current_taskid() }
 }
 
+pub fn set_clear_child_tid(address: VirtualAddress) {
+	unsafe { SCHEDULER.as_ref().unwrap().set_clear_child_tid(address) }
+}
+
 pub struct DisabledPreemption {
 	irq_enabled: bool,
 }
@@ -0,0 +1,173 @@
+use {
+	crate::scheduler::task::{PriorityTaskQueue, Task, TaskId, TaskPriority, NORMAL_PRIORITY},
+	alloc::{collections::BTreeMap, rc::Rc, vec::Vec},
+	core::{cell::RefCell, cmp::max, time::Duration},
+};
+
+/// How the scheduler picks the next task
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Policy {
+	/// The ready task with the highest priority runs, tasks of equal
+	/// priority take turns after each time slice
+	Priority,
+	/// The ready task with the least virtual runtime runs, so every task
+	/// gets a share of the processor weighted by its priority
+	Fair,
+}
+
+impl Policy {
+	/// Policy the scheduler starts with, selected by the `fair-scheduling`
+	/// feature
+	pub const fn default_policy() -> Self {
+		if cfg!(feature = "fair-scheduling") {
+			Policy::Fair
+		} else {
+			Policy::Priority
+		}
+	}
+}
+
+/// Share of the processor a task of `priority` gets relative to others
+fn weight(priority: TaskPriority) -> u64 {
+	u64::from(priority.into()) + 1
+}
+
+/// Returns `runtime` scaled by the weight of `priority`, so that tasks of
+/// higher priority age slower.
+pub fn virtual_runtime(runtime: Duration, priority: TaskPriority) -> u64 {
+	(runtime.as_nanos() * u128::from(weight(NORMAL_PRIORITY)) / u128::from(weight(priority))) as u64
+}
+
+/// Ready tasks ordered by virtual runtime
+pub struct FairTaskQueue {
+	queue: BTreeMap<(u64, TaskId), Rc<RefCell<Task>>>,
+	/// Virtual runtime of the task run last, which newly ready tasks start
+	/// with at least, so that they cannot claim the time they waited
+	min_virtual_runtime: u64,
+}
+
+impl FairTaskQueue {
+	pub const fn new() -> Self {
+		FairTaskQueue {
+			queue: BTreeMap::new(),
+			min_virtual_runtime: 0,
+		}
+	}
+
+	pub fn push(&mut self, task: Rc<RefCell<Task>>) {
+		let key = {
+			let mut borrowed = task.borrow_mut();
+			borrowed.virtual_runtime = max(borrowed.virtual_runtime, self.min_virtual_runtime);
+			(borrowed.virtual_runtime, borrowed.id)
+		};
+
+		self.queue.insert(key, task);
+	}
+
+	pub fn pop(&mut self) -> Option<Rc<RefCell<Task>>> {
+		let ((virtual_runtime, _), task) = self.queue.pop_first()?;
+		self.min_virtual_runtime = max(self.min_virtual_runtime, virtual_runtime);
+
+		Some(task)
+	}
+
+	pub fn remove(&mut self, task: &Rc<RefCell<Task>>) -> bool {
+		let key = {
+			let borrowed = task.borrow();
+			(borrowed.virtual_runtime, borrowed.id)
+		};
+
+		self.queue.remove(&key).is_some()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.queue.is_empty()
+	}
+}
+
+impl Default for FairTaskQueue {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Queue of the ready tasks, ordered as required by the policy
+pub enum ReadyQueue {
+	Priority(PriorityTaskQueue),
+	Fair(FairTaskQueue),
+}
+
+impl ReadyQueue {
+	pub const fn new(policy: Policy) -> Self {
+		match policy {
+			Policy::Priority => ReadyQueue::Priority(PriorityTaskQueue::new()),
+			Policy::Fair => ReadyQueue::Fair(FairTaskQueue::new()),
+		}
+	}
+
+	pub fn policy(&self) -> Policy {
+		match self {
+			ReadyQueue::Priority(_) => Policy::Priority,
+			ReadyQueue::Fair(_) => Policy::Fair,
+		}
+	}
+
+	pub fn push(&mut self, task: Rc<RefCell<Task>>) {
+		match self {
+			ReadyQueue::Priority(queue) => queue.push(task),
+			ReadyQueue::Fair(queue) => queue.push(task),
+		}
+	}
+
+	pub fn pop(&mut self) -> Option<Rc<RefCell<Task>>> {
+		match self {
+			ReadyQueue::Priority(queue) => queue.pop(),
+			ReadyQueue::Fair(queue) => queue.pop(),
+		}
+	}
+
+	/// Removes the ready `task`, which has been queued with `priority`.
+	pub fn remove(&mut self, task: &Rc<RefCell<Task>>, priority: TaskPriority) -> bool {
+		match self {
+			ReadyQueue::Priority(queue) => queue.remove(task, priority),
+			ReadyQueue::Fair(queue) => queue.remove(task),
+		}
+	}
+
+	/// Returns the task which replaces a running task of `priority` whose
+	/// time slice is over or which gives up the processor.
+	pub fn pop_successor(&mut self, priority: TaskPriority) -> Option<Rc<RefCell<Task>>> {
+		match self {
+			ReadyQueue::Priority(queue) => queue.pop_with_priority(priority),
+			ReadyQueue::Fair(queue) => queue.pop(),
+		}
+	}
+
+	/// Returns whether a ready task preempts a running task of `priority`
+	/// before its time slice is over.
+	pub fn preempts(&self, priority: TaskPriority) -> bool {
+		match self {
+			ReadyQueue::Priority(queue) => queue.highest_priority().is_some_and(|highest| highest > priority),
+			ReadyQueue::Fair(_) => false,
+		}
+	}
+
+	/// Returns whether a ready task may take over after a time slice.
+	pub fn has_successor(&self, priority: TaskPriority) -> bool {
+		match self {
+			ReadyQueue::Priority(queue) => queue.highest_priority().is_some_and(|highest| highest >= priority),
+			ReadyQueue::Fair(queue) => !queue.is_empty(),
+		}
+	}
+
+	/// Removes all ready tasks.
+	pub fn drain(&mut self) -> Vec<Rc<RefCell<Task>>> {
+		let mut tasks = Vec::new();
+
+		while let Some(task) = self.pop() {
+			tasks.push(task);
+		}
+
+		tasks
+	}
+}
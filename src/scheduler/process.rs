@@ -0,0 +1,361 @@
+use {
+	crate::{
+		arch::memory::{
+			paging::{get_kernel_root_page_table, BasePageSize, PageSize, PageTableEntryFlags},
+			physical::deallocate,
+			PhysicalAddress, VirtualAddress,
+		},
+		file::{
+			vfs::{
+				standard::{GenericStandardError, GenericStandardInput, GenericStandardOutput},
+				descriptor::{
+					Descriptor, Interface, STANDARD_ERROR, STANDARD_INPUT, STANDARD_OUTPUT,
+				},
+			},
+		},
+		consts::{MAPPING_END, MAPPING_START, USER_ENTRY, USER_STACK_LIMIT},
+		format,
+		scheduler::task::{PriorityTaskQueue, TaskId},
+	},
+	alloc::{
+		collections::{BTreeMap, BTreeSet},
+		sync::Arc,
+		vec::Vec,
+	},
+	core::{
+		cmp::{max, min},
+		time::Duration,
+	},
+};
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub struct ProcessId(u32);
+
+impl ProcessId {
+	pub const fn into(self) -> u32 {
+		self.0
+	}
+
+	pub const fn from(x: u32) -> Self {
+		ProcessId(x)
+	}
+}
+
+impl format::Display for ProcessId {
+	fn fmt(&self, f: &mut format::Formatter) -> format::Result {
+		write!(f, "{}", self.0)
+	}
+}
+
+/// Signal reported to the parent of a process killed by a fault
+pub const SIGNAL_SEGMENTATION_FAULT: i32 = 11;
+
+/// How a process ended, kept until its parent reaps it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitStatus {
+	Exited(i32),
+	Killed(i32),
+}
+
+impl ExitStatus {
+	/// Encodes the status the way wait4 reports it.
+	pub fn wait_status(&self) -> i32 {
+		match self {
+			ExitStatus::Exited(code) => (code & 0xff) << 8,
+			ExitStatus::Killed(signal) => signal & 0x7f,
+		}
+	}
+}
+
+/// The file backing a region and the file offset of the region start.
+#[derive(Clone, Debug)]
+pub struct FileMapping {
+	pub file: Arc<dyn Interface>,
+	pub offset: usize,
+	/// Writes reach the page cache instead of a private copy
+	pub shared: bool,
+}
+
+/// A contiguous range of user virtual memory and the page flags it is mapped with.
+#[derive(Clone, Debug)]
+pub struct MemoryRegion {
+	pub start: VirtualAddress,
+	pub end: VirtualAddress,
+	pub flags: PageTableEntryFlags,
+	pub mapping: Option<FileMapping>,
+}
+
+impl MemoryRegion {
+	pub fn new(start: VirtualAddress, end: VirtualAddress, flags: PageTableEntryFlags) -> Self {
+		MemoryRegion { start, end, flags, mapping: None }
+	}
+
+	pub fn new_file(start: VirtualAddress, end: VirtualAddress, flags: PageTableEntryFlags, mapping: FileMapping) -> Self {
+		MemoryRegion { start, end, flags, mapping: Some(mapping) }
+	}
+
+	/// Returns the part `[start, end)` of this region with the given flags.
+	fn part(&self, start: VirtualAddress, end: VirtualAddress, flags: PageTableEntryFlags) -> Self {
+		let mapping = self.mapping.as_ref().map(|mapping| FileMapping {
+			file: mapping.file.clone(),
+			offset: mapping.offset + (start - self.start).as_usize(),
+			shared: mapping.shared,
+		});
+
+		MemoryRegion { start, end, flags, mapping }
+	}
+
+	pub fn contains(&self, address: VirtualAddress) -> bool {
+		address >= self.start && address < self.end
+	}
+
+	pub fn overlaps(&self, start: VirtualAddress, end: VirtualAddress) -> bool {
+		start < self.end && end > self.start
+	}
+}
+
+/// The root page table of a process and the user regions mapped into it.
+pub struct AddressSpace {
+	pub root_page_table: PhysicalAddress,
+	pub regions: Vec<MemoryRegion>,
+	/// Start of the heap moved by brk, right above the executable image
+	pub heap_start: VirtualAddress,
+	/// Current end of the heap
+	pub program_break: VirtualAddress,
+	/// Size of the stack reserved by the next execve, like RLIMIT_STACK
+	pub stack_limit: usize,
+	/// Inaccessible page right below the stack
+	pub stack_guard: VirtualAddress,
+}
+
+impl AddressSpace {
+	pub fn new_kernel() -> Self {
+		Self::new(get_kernel_root_page_table())
+	}
+
+	pub fn new(root_page_table: PhysicalAddress) -> Self {
+		AddressSpace {
+			root_page_table,
+			regions: Vec::new(),
+			heap_start: VirtualAddress::zero(),
+			program_break: VirtualAddress::zero(),
+			stack_limit: USER_STACK_LIMIT,
+			stack_guard: VirtualAddress::zero(),
+		}
+	}
+
+	pub fn is_kernel(&self) -> bool {
+		self.root_page_table == get_kernel_root_page_table()
+	}
+
+	pub fn insert_region(&mut self, region: MemoryRegion) {
+		let index = self.regions
+			.iter()
+			.position(|other| other.start > region.start)
+			.unwrap_or(self.regions.len());
+
+		self.regions.insert(index, region);
+	}
+
+	pub fn find_region(&self, address: VirtualAddress) -> Option<&MemoryRegion> {
+		self.regions.iter().find(|region| region.contains(address))
+	}
+
+	/// Forgets all user regions, the heap and the stack, called once the
+	/// user pages have been released.
+	pub fn clear(&mut self) {
+		self.regions.clear();
+		self.heap_start = VirtualAddress::zero();
+		self.program_break = VirtualAddress::zero();
+		self.stack_guard = VirtualAddress::zero();
+	}
+
+	/// Returns whether `address` lies in the guard page below the stack.
+	pub fn is_stack_guard(&self, address: VirtualAddress) -> bool {
+		self.stack_guard != VirtualAddress::zero()
+			&& address >= self.stack_guard
+			&& address < self.stack_guard + BasePageSize::SIZE
+	}
+
+	/// Returns whether no region overlaps `[start, end)`.
+	pub fn is_free(&self, start: VirtualAddress, end: VirtualAddress) -> bool {
+		!self.regions.iter().any(|region| region.overlaps(start, end))
+	}
+
+	/// Returns whether every address in `[start, end)` belongs to a region.
+	pub fn is_mapped(&self, start: VirtualAddress, end: VirtualAddress) -> bool {
+		let mut covered = start;
+
+		for region in self.regions.iter().filter(|region| region.overlaps(start, end)) {
+			if region.start > covered {
+				return false;
+			}
+
+			covered = max(covered, region.end);
+		}
+
+		covered >= end
+	}
+
+	/// Returns whether `[start, end)` lies in the part of the address space
+	/// which user mappings may occupy.
+	pub fn is_user_range(start: VirtualAddress, end: VirtualAddress) -> bool {
+		start >= USER_ENTRY && start < end && end <= MAPPING_END
+	}
+
+	/// Finds a free range of `length` bytes for a new mapping. `hint` is
+	/// used if the range starting there is free, otherwise the lowest free
+	/// range above `MAPPING_START` is chosen.
+	pub fn find_free_range(&self, hint: VirtualAddress, length: usize) -> Option<VirtualAddress> {
+		if let Some(end) = hint.as_usize().checked_add(length).map(VirtualAddress::from_usize) {
+			if Self::is_user_range(hint, end) && self.is_free(hint, end) {
+				return Some(hint);
+			}
+		}
+
+		let mut start = MAPPING_START;
+
+		for region in self.regions.iter().filter(|region| region.end > MAPPING_START) {
+			if region.start.as_usize().saturating_sub(start.as_usize()) >= length {
+				break;
+			}
+
+			start = max(start, region.end);
+		}
+
+		if MAPPING_END.as_usize().saturating_sub(start.as_usize()) >= length {
+			Some(start)
+		} else {
+			None
+		}
+	}
+
+	/// Removes `[start, end)` from the regions, splitting regions which
+	/// only partially overlap the range.
+	pub fn remove_region(&mut self, start: VirtualAddress, end: VirtualAddress) {
+		let mut regions = Vec::with_capacity(self.regions.len() + 1);
+
+		for region in self.regions.drain(..) {
+			if !region.overlaps(start, end) {
+				regions.push(region);
+				continue;
+			}
+
+			if region.start < start {
+				regions.push(region.part(region.start, start, region.flags));
+			}
+
+			if region.end > end {
+				regions.push(region.part(end, region.end, region.flags));
+			}
+		}
+
+		self.regions = regions;
+	}
+
+	/// Applies `flags` to `[start, end)`, splitting regions which only
+	/// partially overlap the range.
+	pub fn protect_region(&mut self, start: VirtualAddress, end: VirtualAddress, flags: PageTableEntryFlags) {
+		let mut regions = Vec::with_capacity(self.regions.len() + 2);
+
+		for region in self.regions.drain(..) {
+			if !region.overlaps(start, end) {
+				regions.push(region);
+				continue;
+			}
+
+			if region.start < start {
+				regions.push(region.part(region.start, start, region.flags));
+			}
+
+			regions.push(region.part(max(region.start, start), min(region.end, end), flags));
+
+			if region.end > end {
+				regions.push(region.part(end, region.end, region.flags));
+			}
+		}
+
+		self.regions = regions;
+	}
+}
+
+impl Drop for AddressSpace {
+	fn drop(&mut self) {
+		if !self.is_kernel() {
+			debug!("deallocate page table 0x{:x}.", self.root_page_table);
+			deallocate(self.root_page_table, BasePageSize::SIZE);
+		}
+	}
+}
+
+pub struct Process {
+	pub id: ProcessId,
+	pub parent: Option<ProcessId>,
+	pub address_space: AddressSpace,
+	pub fd_map: BTreeMap<Descriptor, Arc<dyn Interface>>,
+	pub close_on_exec: BTreeSet<Descriptor>,
+	pub exit_status: Option<ExitStatus>,
+	pub threads: Vec<TaskId>,
+	/// Tasks of this process waiting for one of its children to exit
+	pub waiting: PriorityTaskQueue,
+	/// Time the exited threads of this process have been running
+	pub runtime: Duration,
+}
+
+impl Process {
+	pub fn new(id: ProcessId, parent: Option<ProcessId>, address_space: AddressSpace) -> Process {
+		let mut fd_map: BTreeMap<Descriptor, Arc<dyn Interface>> = BTreeMap::new();
+		fd_map
+			.try_insert(STANDARD_INPUT, Arc::new(GenericStandardInput::new()))
+			.unwrap();
+		fd_map
+			.try_insert(STANDARD_OUTPUT, Arc::new(GenericStandardOutput::new()))
+			.unwrap();
+		fd_map
+			.try_insert(STANDARD_ERROR, Arc::new(GenericStandardError::new()))
+			.unwrap();
+
+		Process {
+			id,
+			parent,
+			address_space,
+			fd_map,
+			close_on_exec: BTreeSet::new(),
+			exit_status: None,
+			threads: Vec::new(),
+			waiting: PriorityTaskQueue::new(),
+			runtime: Duration::ZERO,
+		}
+	}
+
+	/// Returns the child of a fork: a copy of this process with the given
+	/// root page table, which shares the user pages copy-on-write.
+	pub fn fork(&self, id: ProcessId, root_page_table: PhysicalAddress) -> Process {
+		Process {
+			id,
+			parent: Some(self.id),
+			address_space: AddressSpace {
+				root_page_table,
+				regions: self.address_space.regions.clone(),
+				heap_start: self.address_space.heap_start,
+				program_break: self.address_space.program_break,
+				stack_limit: self.address_space.stack_limit,
+				stack_guard: self.address_space.stack_guard,
+			},
+			fd_map: self.fd_map.clone(),
+			close_on_exec: self.close_on_exec.clone(),
+			exit_status: None,
+			threads: Vec::new(),
+			waiting: PriorityTaskQueue::new(),
+			runtime: Duration::ZERO,
+		}
+	}
+
+	/// Closes every descriptor opened with `CLOSE_ON_EXEC`, called when
+	/// the process image is replaced.
+	pub fn close_descriptors_on_exec(&mut self) {
+		for fd in core::mem::take(&mut self.close_on_exec) {
+			self.fd_map.remove(&fd);
+		}
+	}
+}
@@ -3,7 +3,11 @@ use {
 	crate::{
 		scheduler::error::Error,
 		consts::*,
+		time,
 		scheduler::task::*,
+		scheduler::process::*,
+		scheduler::policy::{virtual_runtime, Policy, ReadyQueue},
+		scheduler::timer::{Timer, TimerAction, TimerId, TimerQueue},
 		file::{
 			vfs::{
 				descriptor::{Descriptor, Interface},
@@ -12,46 +16,88 @@ use {
 		arch::{
 			memory::{
 				PhysicalAddress, VirtualAddress,
-				paging::drop_user_space,
+				paging::{create_usr_pgd, drop_user_space, fork_usr_pgd},
+				user::{put_user, user_to_physical},
+			},
+			kernel::{
+				processor::fpu::{clear_task_switched, set_task_switched, FpuState},
+				scheduling::switch,
 			},
-			kernel::scheduling::switch,
 		},
 	},
 	core::{
 		cell::RefCell,
+		cmp::max,
+		mem,
+		time::Duration,
 		sync::atomic::{AtomicU32, Ordering},
 	},
 	alloc::{
+		boxed::Box,
 		rc::Rc,
+		string::String,
+		vec::Vec,
 		sync::Arc,
 		collections::{BTreeMap, VecDeque}
 	},
 };
 
+/// Entry point of the tasks created by `spawn_with`
+extern "C" fn run_entry() {
+	if let Some(entry) = super::take_entry() {
+		entry();
+	}
+}
+
 static TID_COUNTER: AtomicU32 = AtomicU32::new(0);
+static PID_COUNTER: AtomicU32 = AtomicU32::new(0);
 
 pub struct Scheduler {
 	current: Rc<RefCell<Task>>,
 	idle: Rc<RefCell<Task>>,
-	ready: PriorityTaskQueue,
+	ready: ReadyQueue,
 	finished: VecDeque<TaskId>,
 	tasks: BTreeMap<TaskId, Rc<RefCell<Task>>>,
+	kernel: Rc<RefCell<Process>>,
+	processes: BTreeMap<ProcessId, Rc<RefCell<Process>>>,
+	/// Tasks blocked in futex wait, keyed by the frame address of the word
+	futexes: BTreeMap<PhysicalAddress, PriorityTaskQueue>,
+	/// Task whose registers are loaded in the FPU
+	fpu_owner: Option<Rc<RefCell<Task>>>,
+	/// Sleeping tasks and kernel timers ordered by deadline
+	timers: TimerQueue,
+	/// Timer interrupts a task runs before others of equal priority
+	time_slice: u64,
+	/// Monotonic time the current task has been charged up to
+	last_switch: Duration,
 }
 
 impl Scheduler {
 	pub fn new() -> Scheduler {
+		let process_id = ProcessId::from(PID_COUNTER.fetch_add(1, Ordering::SeqCst));
+		let kernel = Rc::new(RefCell::new(Process::new(process_id, None, AddressSpace::new_kernel())));
 		let task_id = TaskId::from(TID_COUNTER.fetch_add(1, Ordering::SeqCst));
-		let idle = Rc::new(RefCell::new(Task::new_idle(task_id)));
+		let idle = Rc::new(RefCell::new(Task::new_idle(task_id, kernel.clone())));
 		let mut tasks = BTreeMap::new();
+		let mut processes = BTreeMap::new();
 
+		kernel.borrow_mut().threads.push(task_id);
 		tasks.insert(task_id, idle.clone());
+		processes.insert(process_id, kernel.clone());
 
 		Scheduler {
 			current: idle.clone(),
 			idle: idle.clone(),
-			ready: PriorityTaskQueue::new(),
+			ready: ReadyQueue::new(Policy::default_policy()),
 			finished: VecDeque::<TaskId>::new(),
 			tasks,
+			kernel,
+			processes,
+			futexes: BTreeMap::new(),
+			fpu_owner: None,
+			timers: TimerQueue::new(),
+			time_slice: TIME_SLICE,
+			last_switch: Duration::ZERO,
 		}
 	}
 
@@ -65,23 +111,167 @@ impl Scheduler {
 		}
 	}
 
+	fn get_pid(&self) -> ProcessId {
+		loop {
+			let id = ProcessId::from(PID_COUNTER.fetch_add(1, Ordering::SeqCst));
+
+			if !self.processes.contains_key(&id) {
+				return id;
+			}
+		}
+	}
+
+	fn create_task(&mut self, func: extern "C" fn(), priority: TaskPriority, process: Rc<RefCell<Process>>) -> Result<TaskId, Error> {
+		let priority_number: usize = priority.into().into();
+
+		if priority_number >= NO_PRIORITIES {
+			return Err(Error::BadPriority);
+		}
+
+		let tid = self.get_tid();
+		let task = Rc::new(RefCell::new(Task::new(tid, TaskStatus::Ready, priority, process.clone())));
+
+		task.borrow_mut().create_stack_frame(func);
+		process.borrow_mut().threads.push(tid);
+
+		self.ready.push(task.clone());
+		self.tasks.insert(tid, task);
+
+		info!("creating task {}.", tid);
+
+		Ok(tid)
+	}
+
 	pub fn spawn(&mut self, func: extern "C" fn(), priority: TaskPriority) -> Result<TaskId, Error> {
+		save_interrupt(|| self.create_task(func, priority, self.kernel.clone()))
+	}
+
+	/// Creates a kernel task named `name` which runs `entry`.
+	pub fn spawn_with(&mut self, name: &str, entry: Box<dyn FnOnce()>, priority: TaskPriority) -> Result<TaskId, Error> {
 		let closure = || {
-			let priority_number: usize = priority.into().into();
+			let tid = self.create_task(run_entry, priority, self.kernel.clone())?;
+			let mut task = self.tasks[&tid].borrow_mut();
+
+			task.name = String::from(name);
+			task.entry = Some(entry);
+
+			info!("task {} is named `{}`.", tid, name);
+
+			Ok(tid)
+		};
 
-			if priority_number >= NO_PRIORITIES {
-				return Err(Error::BadPriority);
+		save_interrupt(closure)
+	}
+
+	/// Takes the closure the current task has been spawned with.
+	pub fn take_entry(&mut self) -> Option<Box<dyn FnOnce()>> {
+		save_interrupt(|| self.current.borrow_mut().entry.take())
+	}
+
+	/// Returns `true` if the task `id` has finished. Otherwise the current
+	/// task is queued until it finishes.
+	pub fn join(&mut self, id: TaskId) -> bool {
+		let closure = || {
+			if id == self.current.borrow().id {
+				panic!("task {} is unable to join itself.", id);
 			}
 
+			// finished tasks are invalidated once switched away from and
+			// dropped by a later schedule
+			let running = |task: &Rc<RefCell<Task>>| {
+				!matches!(task.borrow().status, TaskStatus::Finished | TaskStatus::Invalid)
+			};
+
+			match self.tasks.get(&id).cloned() {
+				Some(task) if running(&task) => {
+					let current = self.block_current_task();
+					task.borrow_mut().waiting.push(current);
+
+					false
+				}
+				_ => true,
+			}
+		};
+
+		save_interrupt(closure)
+	}
+
+	pub fn spawn_process(&mut self, func: extern "C" fn(), priority: TaskPriority) -> Result<ProcessId, Error> {
+		let closure = || {
+			let pid = self.get_pid();
+			let parent = self.current.borrow().process.borrow().id;
+			let address_space = AddressSpace::new(create_usr_pgd());
+			let process = Rc::new(RefCell::new(Process::new(pid, Some(parent), address_space)));
+
+			self.create_task(func, priority, process.clone())?;
+			self.processes.insert(pid, process);
+
+			info!("creating process {}.", pid);
+
+			Ok(pid)
+		};
+
+		save_interrupt(closure)
+	}
+
+	pub fn fork(&mut self, user_stack_pointer: VirtualAddress) -> Result<ProcessId, Error> {
+		let closure = || {
+			let pid = self.get_pid();
 			let tid = self.get_tid();
-			let task = Rc::new(RefCell::new(Task::new(tid, TaskStatus::Ready, priority)));
+			let (parent, priority) = {
+				let current = self.current.borrow();
+				(current.process.clone(), current.base_priority)
+			};
+
+			let process = Rc::new(RefCell::new(parent.borrow().fork(pid, fork_usr_pgd())));
+			let task = Rc::new(RefCell::new(Task::new(tid, TaskStatus::Ready, priority, process.clone())));
+
+			task.borrow_mut().create_fork_frame(user_stack_pointer);
+			task.borrow_mut().fpu = self.copy_fpu_state();
+			process.borrow_mut().threads.push(tid);
+
+			self.ready.push(task.clone());
+			self.tasks.insert(tid, task);
+			self.processes.insert(pid, process);
+
+			info!("fork process {} from {}.", pid, parent.borrow().id);
+
+			Ok(pid)
+		};
+
+		save_interrupt(closure)
+	}
+
+	/// Creates a thread of the current process which leaves the kernel like
+	/// the current invoke, restoring the user registers saved at
+	/// `user_stack_pointer`.
+	pub fn spawn_thread(&mut self, user_stack_pointer: VirtualAddress, thread_pointer: Option<usize>, clear_child_tid: VirtualAddress) -> Result<TaskId, Error> {
+		let closure = || {
+			let tid = self.get_tid();
+			let (process, priority) = {
+				let current = self.current.borrow();
+				(current.process.clone(), current.base_priority)
+			};
+
+			let task = Rc::new(RefCell::new(Task::new(tid, TaskStatus::Ready, priority, process.clone())));
+
+			{
+				let mut task = task.borrow_mut();
+				task.create_fork_frame(user_stack_pointer);
+				task.clear_child_tid = clear_child_tid;
+				task.fpu = self.copy_fpu_state();
 
-			task.borrow_mut().create_stack_frame(func);
+				if let Some(thread_pointer) = thread_pointer {
+					task.set_thread_pointer(thread_pointer);
+				}
+			}
+
+			process.borrow_mut().threads.push(tid);
 
 			self.ready.push(task.clone());
 			self.tasks.insert(tid, task);
 
-			info!("creating task {}.", tid);
+			info!("create thread {} in process {}.", tid, process.borrow().id);
 
 			Ok(tid)
 		};
@@ -89,17 +279,284 @@ impl Scheduler {
 		save_interrupt(closure)
 	}
 
-	fn cleanup(&mut self) {
-		drop_user_space();
+	fn owns_fpu(&self) -> bool {
+		self.fpu_owner.as_ref().is_some_and(|owner| Rc::ptr_eq(owner, &self.current))
+	}
+
+	/// Returns a copy of the FPU registers of the current task.
+	fn copy_fpu_state(&self) -> FpuState {
+		// the registers in the FPU are newer than the saved ones
+		if self.owns_fpu() {
+			self.current.borrow_mut().fpu.save();
+		}
+
+		self.current.borrow().fpu.clone()
+	}
+
+	/// Loads the FPU registers of the current task, saving those of the
+	/// previous owner. Called on the first use of the FPU after a switch.
+	pub fn restore_fpu_state(&mut self) {
+		let closure = || {
+			clear_task_switched();
+
+			if self.owns_fpu() {
+				return;
+			}
+
+			if let Some(owner) = self.fpu_owner.take() {
+				owner.borrow_mut().fpu.save();
+			}
+
+			self.current.borrow_mut().fpu.restore();
+			self.fpu_owner = Some(self.current.clone());
+		};
+
+		save_interrupt(closure)
+	}
+
+	/// Resets the FPU registers of the current task, called when the
+	/// process image is replaced.
+	pub fn reset_fpu_state(&mut self) {
+		let closure = || {
+			if self.owns_fpu() {
+				self.fpu_owner = None;
+				set_task_switched();
+			}
+
+			self.current.borrow_mut().fpu = FpuState::new();
+		};
+
+		save_interrupt(closure)
+	}
+
+	/// Blocks the current task until the timer interrupt `deadline` since
+	/// boot has been counted.
+	pub fn sleep_until_tick(&mut self, deadline: u64) {
+		let closure = || {
+			if deadline <= time::ticks() {
+				return false;
+			}
+
+			let task = self.block_current_task();
+			self.timers.insert(deadline, 0, TimerAction::Wakeup(task));
+
+			true
+		};
+
+		if save_interrupt(closure) {
+			self.reschedule();
+		}
+	}
+
+	/// Runs `callback` from the timer interrupt at tick `deadline` and, if
+	/// `period` is not zero, every `period` ticks after it.
+	pub fn add_timer(&mut self, deadline: u64, period: u64, callback: Box<dyn FnMut()>) -> TimerId {
+		save_interrupt(|| self.timers.insert(deadline, period, TimerAction::Callback(callback)))
+	}
+
+	pub fn cancel_timer(&mut self, id: TimerId) -> bool {
+		save_interrupt(|| self.timers.cancel(id))
+	}
+
+	/// Wakes the sleeping tasks whose deadline is `now` or earlier and returns
+	/// the expired callback timers.
+	///
+	/// The callbacks are run by the caller after this borrow of the
+	/// scheduler has ended, because they may call into the scheduler.
+	pub fn expire_timers(&mut self, now: u64) -> Vec<Timer> {
+		let closure = || {
+			let mut expired = Vec::new();
+
+			while let Some(timer) = self.timers.pop_expired(now) {
+				match &timer.action {
+					TimerAction::Wakeup(task) => self.wakeup_task(task.clone()),
+					TimerAction::Callback(_) => expired.push(timer),
+				}
+			}
+
+			expired
+		};
+
+		save_interrupt(closure)
+	}
+
+	/// Queues a periodic timer again after its callback ran.
+	pub fn rearm_timer(&mut self, timer: Timer) {
+		save_interrupt(|| self.timers.rearm(timer))
+	}
+
+	/// Blocks the current task until `futex_wake` is called for `key`. The
+	/// caller checks the futex word with interrupts disabled and reschedules.
+	pub fn futex_wait(&mut self, key: PhysicalAddress) {
+		let closure = || {
+			let task = self.block_current_task();
+			self.futexes.entry(key).or_insert_with(PriorityTaskQueue::new).push(task);
+		};
+
+		save_interrupt(closure)
+	}
+
+	/// Wakes up to `count` tasks waiting on `key` and returns their number.
+	pub fn futex_wake(&mut self, key: PhysicalAddress, count: usize) -> usize {
+		let closure = || {
+			let mut woken = 0;
+
+			while woken < count {
+				let task = match self.futexes.get_mut(&key).and_then(|queue| queue.pop_blocked()) {
+					Some(task) => task,
+					None => break,
+				};
+
+				self.wakeup_task(task);
+				woken += 1;
+			}
+
+			if self.futexes.get(&key).is_some_and(|queue| queue.is_empty()) {
+				self.futexes.remove(&key);
+			}
+
+			woken
+		};
+
+		save_interrupt(closure)
+	}
+
+	fn cleanup(&mut self, status: ExitStatus) {
+		let (id, process) = {
+			let current = self.current.borrow();
+			(current.id, current.process.clone())
+		};
+
+		if self.owns_fpu() {
+			self.fpu_owner = None;
+		}
+
+		self.account();
+		process.borrow_mut().runtime += self.current.borrow().runtime;
+
+		// tell threads joining this one that it has exited
+		let clear_child_tid = self.current.borrow().clear_child_tid;
+
+		if clear_child_tid != VirtualAddress::zero() {
+			// the word may already be unmapped, which is ignored on exit
+			if let Ok(key) = user_to_physical(clear_child_tid.as_usize()) {
+				let _ = put_user(clear_child_tid.as_mut_ptr::<u32>(), 0);
+				self.futex_wake(key, 1);
+			}
+		}
+
+		let last_thread = {
+			let mut process = process.borrow_mut();
+			process.threads.retain(|tid| *tid != id);
+			process.threads.is_empty()
+		};
+
+		if last_thread {
+			if !process.borrow().address_space.is_kernel() {
+				drop_user_space();
+				process.borrow_mut().address_space.clear();
+			}
+
+			process.borrow_mut().fd_map.clear();
+			process.borrow_mut().exit_status = Some(status);
+
+			let pid = process.borrow().id;
+			self.release_children(pid);
+
+			// only user processes wait for their children, orphans of the
+			// kernel are reaped right away
+			let kernel_id = self.kernel.borrow().id;
+			let parent = process.borrow().parent.filter(|parent| *parent != kernel_id);
+
+			match parent.and_then(|parent| self.processes.get(&parent).cloned()) {
+				Some(parent) => {
+					info!("process {} exited with {:?}.", pid, status);
+
+					while let Some(task) = parent.borrow_mut().waiting.pop() {
+						self.wakeup_task(task);
+					}
+				}
+				None => {
+					self.processes.remove(&pid);
+				}
+			}
+		}
+
+		// wake the tasks joining this one
+		let mut waiting = mem::replace(&mut self.current.borrow_mut().waiting, PriorityTaskQueue::new());
+
+		while let Some(task) = waiting.pop() {
+			self.wakeup_task(task);
+		}
 
 		self.current.borrow_mut().status = TaskStatus::Finished;
 	}
 
-	pub fn exit(&mut self) -> ! {
+	/// Reaps the exited children of the process `pid` and hands the running
+	/// ones over to the kernel process.
+	fn release_children(&mut self, pid: ProcessId) {
+		let kernel_id = self.kernel.borrow().id;
+
+		self.processes.retain(|_, process| {
+			let mut process = process.borrow_mut();
+
+			if process.parent != Some(pid) {
+				return true;
+			}
+
+			process.parent = Some(kernel_id);
+			process.exit_status.is_none()
+		});
+	}
+
+	/// Reaps an exited child of the current process, either the child `pid`
+	/// or any child. Returns `None` if matching children are still running
+	/// and, if `block` is set, queues the current task until one exits.
+	pub fn wait_child(&mut self, pid: Option<ProcessId>, block: bool) -> Result<Option<(ProcessId, ExitStatus)>, Error> {
+		let closure = || {
+			let parent = self.current.borrow().process.clone();
+			let parent_id = parent.borrow().id;
+			let mut found = false;
+			let mut exited = None;
+
+			for (id, process) in self.processes.iter() {
+				let process = process.borrow();
+
+				if process.parent != Some(parent_id) || pid.is_some_and(|pid| pid != *id) {
+					continue;
+				}
+
+				found = true;
+
+				if let Some(status) = process.exit_status {
+					exited = Some((*id, status));
+					break;
+				}
+			}
+
+			if !found {
+				return Err(Error::NoChildProcess);
+			}
+
+			if let Some((id, _)) = exited {
+				debug!("reap process {}.", id);
+				self.processes.remove(&id);
+			} else if block {
+				let task = self.block_current_task();
+				parent.borrow_mut().waiting.push(task);
+			}
+
+			Ok(exited)
+		};
+
+		save_interrupt(closure)
+	}
+
+	pub fn exit(&mut self, code: i32) -> ! {
 		let closure = || {
 			if self.current.borrow().status != TaskStatus::Idle {
 				info!("finished task with id {}.", self.current.borrow().id);
-				self.cleanup();
+				self.cleanup(ExitStatus::Exited(code));
 			} else {
 				panic!("unable to terminate idle task.");
 			}
@@ -112,11 +569,84 @@ impl Scheduler {
 		panic!("exit failed!");
 	}
 
+	/// Terminates all threads of the current process except the current
+	/// task, which is left as the only thread.
+	pub fn terminate_other_threads(&mut self) {
+		let closure = || {
+			let (id, process) = {
+				let current = self.current.borrow();
+				(current.id, current.process.clone())
+			};
+
+			let threads = mem::take(&mut process.borrow_mut().threads);
+
+			for tid in threads.iter().filter(|tid| **tid != id) {
+				let task = match self.tasks.get(tid).cloned() {
+					Some(task) => task,
+					None => continue,
+				};
+
+				info!("terminate thread {} of process {}.", tid, process.borrow().id);
+
+				let (status, priority) = {
+					let borrowed = task.borrow();
+					(borrowed.status, borrowed.priority)
+				};
+
+				if status == TaskStatus::Ready {
+					self.ready.remove(&task, priority);
+				}
+
+				if self.fpu_owner.as_ref().is_some_and(|owner| Rc::ptr_eq(owner, &task)) {
+					self.fpu_owner = None;
+				}
+
+				// a blocked task stays in its wait queue. Locks and
+				// futexes skip it, they only hand over to blocked tasks.
+				process.borrow_mut().runtime += task.borrow().runtime;
+				task.borrow_mut().status = TaskStatus::Invalid;
+				self.finished.push_back(*tid);
+
+				let mut waiting = mem::replace(&mut task.borrow_mut().waiting, PriorityTaskQueue::new());
+
+				while let Some(task) = waiting.pop() {
+					self.wakeup_task(task);
+				}
+			}
+
+			process.borrow_mut().threads.push(id);
+		};
+
+		save_interrupt(closure)
+	}
+
+	/// Terminates the other threads of the current process, which exits
+	/// with `code` together with the current task.
+	pub fn exit_group(&mut self, code: i32) -> ! {
+		let closure = || {
+			if self.current.borrow().status == TaskStatus::Idle {
+				panic!("unable to terminate idle task.");
+			}
+
+			self.terminate_other_threads();
+
+			// the current task is the last thread and records the status
+			info!("finished task with id {}.", self.current.borrow().id);
+			self.cleanup(ExitStatus::Exited(code));
+		};
+
+		save_interrupt(closure);
+
+		self.reschedule();
+
+		panic!("exit_group failed!");
+	}
+
 	pub fn abort(&mut self) -> ! {
 		let closure = || {
 			if self.current.borrow().status != TaskStatus::Idle {
 				info!("abort task with id {}.", self.current.borrow().id);
-				self.cleanup();
+				self.cleanup(ExitStatus::Killed(SIGNAL_SEGMENTATION_FAULT));
 			} else {
 				panic!("unable to terminate idle task.");
 			}
@@ -144,6 +674,26 @@ impl Scheduler {
 		save_interrupt(closure)
 	}
 
+	/// Changes the effective priority of `task`, which is queued again if it
+	/// is ready.
+	pub fn change_priority(&mut self, task: &Rc<RefCell<Task>>, priority: TaskPriority) {
+		let closure = || {
+			let (status, old_priority) = {
+				let borrowed = task.borrow();
+				(borrowed.status, borrowed.priority)
+			};
+
+			if status == TaskStatus::Ready && self.ready.remove(task, old_priority) {
+				task.borrow_mut().priority = priority;
+				self.ready.push(task.clone());
+			} else {
+				task.borrow_mut().priority = priority;
+			}
+		};
+
+		save_interrupt(closure);
+	}
+
 	pub fn wakeup_task(&mut self, task: Rc<RefCell<Task>>) {
 		let closure = || {
 			if task.borrow().status == TaskStatus::Blocked {
@@ -161,10 +711,11 @@ impl Scheduler {
         &mut self,
         io_interface: Arc<dyn Interface>,
 	) -> Result<Descriptor, Error> {
+		let process = self.current.borrow().process.clone();
 		let new_fd = || -> Result<Descriptor, Error> {
 			let mut fd: Descriptor = 0;
 			loop {
-				if !self.current.borrow().fd_map.contains_key(&fd) {
+				if !process.borrow().fd_map.contains_key(&fd) {
 					break Ok(fd);
 				} else if fd == Descriptor::MAX {
 					break Err(Error::ValueOverflow);
@@ -175,7 +726,7 @@ impl Scheduler {
 		};
 
 		let fd = new_fd()?;
-		self.current
+		process
 			.borrow_mut()
 			.fd_map
 			.insert(fd, io_interface.clone());
@@ -184,11 +735,11 @@ impl Scheduler {
 	}
 
 	pub fn remove_io_interface(&self, fd: Descriptor) -> Result<Arc<dyn Interface>, Error> {
-		self.current
-			.borrow_mut()
-			.fd_map
-			.remove(&fd)
-			.ok_or(Error::BadFileDescriptor)
+		let process = self.current.borrow().process.clone();
+		let mut process = process.borrow_mut();
+
+		process.close_on_exec.remove(&fd);
+		process.fd_map.remove(&fd).ok_or(Error::BadFileDescriptor)
 	}
 
 	pub fn get_io_interface(
@@ -196,31 +747,54 @@ impl Scheduler {
 		fd: Descriptor,
 	) -> Result<Arc<dyn Interface>, Error> {
 		let closure = || {
-			if let Some(io_interface) = self.current.borrow().fd_map.get(&fd) {
+			if let Some(io_interface) = self.current.borrow().process.borrow().fd_map.get(&fd) {
 				Ok(io_interface.clone())
 			} else {
-				Err(Error::FileNotFound)
+				Err(Error::BadFileDescriptor)
 			}
 		};
 
 		save_interrupt(closure)
 	}
 
+	pub fn has_pending_tasks(&self) -> bool {
+		save_interrupt(|| {
+			self.tasks.values().any(|task| {
+				matches!(
+					task.borrow().status,
+					TaskStatus::Ready | TaskStatus::Running | TaskStatus::Blocked
+				)
+			})
+		})
+	}
+
 	pub fn get_current_taskid(&self) -> TaskId {
 		save_interrupt(|| self.current.borrow().id)
 	}
 
+	pub fn set_clear_child_tid(&self, address: VirtualAddress) {
+		save_interrupt(|| self.current.borrow_mut().clear_child_tid = address)
+	}
+
 	#[no_mangle]
 	pub fn get_current_interrupt_stack(&self) -> VirtualAddress {
 		save_interrupt(|| (*self.current.borrow().stack).interrupt_top())
 	}
 
+	pub fn get_current_task(&self) -> Rc<RefCell<Task>> {
+		save_interrupt(|| self.current.clone())
+	}
+
+	pub fn get_current_process(&self) -> Rc<RefCell<Process>> {
+		save_interrupt(|| self.current.borrow().process.clone())
+	}
+
 	pub fn get_root_page_table(&self) -> PhysicalAddress {
-		self.current.borrow().root_page_table
+		self.current.borrow().process.borrow().address_space.root_page_table
 	}
 
 	pub fn set_root_page_table(&self, addr: PhysicalAddress) {
-		self.current.borrow_mut().root_page_table = addr;
+		self.current.borrow().process.borrow_mut().address_space.root_page_table = addr;
 	}
 
 	pub fn schedule(&mut self) {
@@ -242,7 +816,7 @@ impl Scheduler {
 
 		let mut next_task;
 		if current_status == TaskStatus::Running {
-			next_task = self.ready.pop_with_priority(current_priority);
+			next_task = self.ready.pop_successor(current_priority);
 		} else {
 			next_task = self.ready.pop();
 		}
@@ -259,9 +833,12 @@ impl Scheduler {
 			let (new_id, new_stack_pointer) = {
 				let mut borrowed = new_task.borrow_mut();
 				borrowed.status = TaskStatus::Running;
+				borrowed.slice = 0;
 				(borrowed.id, borrowed.last_stack_pointer)
 			};
 
+			self.account();
+
 			if current_status == TaskStatus::Running {
 				debug!("add task {} to ready queue.", current_id);
 				self.current.borrow_mut().status = TaskStatus::Ready;
@@ -288,6 +865,94 @@ impl Scheduler {
 		}
 	}
 
+	/// Charges the time since the last switch to the current task.
+	fn account(&mut self) {
+		let now = time::monotonic();
+		let elapsed = now.saturating_sub(self.last_switch);
+		let mut current = self.current.borrow_mut();
+
+		current.runtime += elapsed;
+		current.virtual_runtime += virtual_runtime(elapsed, current.priority);
+		self.last_switch = now;
+	}
+
+	/// Returns the time the current task or, if `process` is set, all
+	/// threads of its process have been running.
+	pub fn get_runtime(&self, process: bool) -> Duration {
+		let closure = || {
+			let current = self.current.borrow();
+			let running = time::monotonic().saturating_sub(self.last_switch);
+
+			if !process {
+				return current.runtime + running;
+			}
+
+			let threads: Duration = self.tasks
+				.values()
+				.filter(|task| !Rc::ptr_eq(task, &self.current))
+				.map(|task| task.borrow())
+				.filter(|task| Rc::ptr_eq(&task.process, &current.process))
+				.map(|task| task.runtime)
+				.sum();
+
+			let exited = current.process.borrow().runtime;
+
+			exited + threads + current.runtime + running
+		};
+
+		save_interrupt(closure)
+	}
+
+	/// Counts a timer interrupt against the time slice of the current task
+	/// and switches tasks if the policy demands it.
+	pub fn timer_tick(&mut self) {
+		let closure = || {
+			let (priority, status, slice) = {
+				let mut current = self.current.borrow_mut();
+				current.slice += 1;
+				(current.priority, current.status, current.slice)
+			};
+
+			status != TaskStatus::Running
+				|| self.ready.preempts(priority)
+				|| (slice >= self.time_slice && self.ready.has_successor(priority))
+		};
+
+		if save_interrupt(closure) {
+			self.reschedule();
+		}
+	}
+
+	/// Sets the number of timer interrupts a task runs before tasks of
+	/// equal priority or, with the fair policy, any ready task take over.
+	pub fn set_time_slice(&mut self, ticks: u64) {
+		save_interrupt(|| self.time_slice = max(ticks, 1))
+	}
+
+	pub fn get_policy(&self) -> Policy {
+		self.ready.policy()
+	}
+
+	/// Switches to `policy`, moving the ready tasks into its queue.
+	pub fn set_policy(&mut self, policy: Policy) {
+		let closure = || {
+			if self.ready.policy() == policy {
+				return;
+			}
+
+			let tasks = self.ready.drain();
+			self.ready = ReadyQueue::new(policy);
+
+			for task in tasks {
+				self.ready.push(task);
+			}
+
+			info!("switch to scheduling policy {:?}.", policy);
+		};
+
+		save_interrupt(closure)
+	}
+
 	pub fn reschedule(&mut self) {
 		save_interrupt(|| self.schedule());
 	}
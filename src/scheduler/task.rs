@@ -3,34 +3,21 @@
 use {
 	crate::{
 		arch::{
-			kernel::processor::utilities::most_significant_bit,
+			kernel::processor::{fpu::FpuState, utilities::most_significant_bit},
 			memory::{
-				get_boot_stack, paging::{
-					get_kernel_root_page_table,
-					BasePageSize, PageSize,
-				},
-				physical::deallocate,
-				PhysicalAddress,
+				get_boot_stack,
 				VirtualAddress,
 			},
 		},
 		consts::*,
-		file::{
-			vfs::{
-				standard::{GenericStandardError, GenericStandardInput, GenericStandardOutput},
-				
-				descriptor::{
-					Descriptor, Interface, STANDARD_ERROR, STANDARD_INPUT, STANDARD_OUTPUT,
-				},
-			},
-		},
 		format,
+		scheduler::process::Process,
+		sync::mutex::RawMutex,
 	},
 	alloc::{
-		boxed::Box, collections::{BTreeMap, VecDeque}, rc::Rc,
-		sync::Arc,
+		boxed::Box, collections::{BTreeMap, VecDeque}, rc::Rc, string::String,
 	},
-	core::cell::RefCell,
+	core::{cell::RefCell, cmp::max, time::Duration},
 };
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -124,6 +111,42 @@ impl PriorityTaskQueue {
 		None
 	}
 
+	/// Removes and returns the waiter of highest priority which is still
+	/// blocked. Tasks terminated while they were waiting are dropped.
+	pub fn pop_blocked(&mut self) -> Option<Rc<RefCell<Task>>> {
+		while let Some(task) = self.pop() {
+			if task.borrow().status == TaskStatus::Blocked {
+				return Some(task);
+			}
+		}
+
+		None
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.priority_bitmap == 0
+	}
+
+	pub fn highest_priority(&self) -> Option<TaskPriority> {
+		most_significant_bit(self.priority_bitmap).map(|i| TaskPriority::from(i as u8))
+	}
+
+	/// Removes `task`, which has been queued with `priority`.
+	pub fn remove(&mut self, task: &Rc<RefCell<Task>>, priority: TaskPriority) -> bool {
+		let i: usize = priority.into().into();
+
+		if let Some(position) = self.queues[i].iter().position(|queued| Rc::ptr_eq(queued, task)) {
+			self.queues[i].remove(position);
+			if self.queues[i].is_empty() {
+				self.priority_bitmap &= !(1 << i);
+			}
+
+			true
+		} else {
+			false
+		}
+	}
+
 	pub fn pop_with_priority(&mut self, priority: TaskPriority) -> Option<Rc<RefCell<Task>>> {
 		if let Some(i) = most_significant_bit(self.priority_bitmap) {
 			if i >= priority.into().into() {
@@ -186,63 +209,91 @@ impl Stack for TaskStack {
 #[repr(align(64))]
 pub struct Task {
 	pub id: TaskId,
+	/// Effective priority, raised above `base_priority` while the task
+	/// holds a mutex a task of higher priority waits for
 	pub priority: TaskPriority,
+	pub base_priority: TaskPriority,
 	pub status: TaskStatus,
 	pub last_stack_pointer: VirtualAddress,
 	pub stack: Box<dyn Stack>,
-	pub root_page_table: PhysicalAddress,
-	pub fd_map: BTreeMap<Descriptor, Arc<dyn Interface>>,
+	pub process: Rc<RefCell<Process>>,
+	/// User address cleared on exit, registered by set_tid_address
+	pub clear_child_tid: VirtualAddress,
+	/// Registers of the FPU while another task owns it
+	pub fpu: FpuState,
+	/// Name given by `spawn_with`, empty for other tasks
+	pub name: String,
+	/// Closure run by a task created with `spawn_with`
+	pub entry: Option<Box<dyn FnOnce()>>,
+	/// Tasks waiting for this task to finish
+	pub waiting: PriorityTaskQueue,
+	/// Time the task has been running
+	pub runtime: Duration,
+	/// Runtime weighted by priority, which orders the fair policy
+	pub virtual_runtime: u64,
+	/// Timer interrupts since the task has been switched to
+	pub slice: u64,
+	/// Highest priority of the waiters of each held mutex, keyed by the
+	/// address of the mutex
+	pub donations: BTreeMap<usize, TaskPriority>,
+	/// Mutex the task waits for
+	pub blocked_on: Option<*const RawMutex>,
 }
 
 impl Task {
-	pub fn new_idle(id: TaskId) -> Task {
+	pub fn new_idle(id: TaskId, process: Rc<RefCell<Process>>) -> Task {
 		Task {
 			id,
 			priority: LOW_PRIORITY,
+			base_priority: LOW_PRIORITY,
 			status: TaskStatus::Idle,
 			last_stack_pointer: VirtualAddress::zero(),
 			stack: Box::new(get_boot_stack()),
-			root_page_table: get_kernel_root_page_table(),
-			fd_map: BTreeMap::new(),
+			process,
+			clear_child_tid: VirtualAddress::zero(),
+			fpu: FpuState::new(),
+			name: String::new(),
+			entry: None,
+			waiting: PriorityTaskQueue::new(),
+			runtime: Duration::ZERO,
+			virtual_runtime: 0,
+			slice: 0,
+			donations: BTreeMap::new(),
+			blocked_on: None,
 		}
 	}
 
-	pub fn new(id: TaskId, status: TaskStatus, priority: TaskPriority) -> Task {
-		let mut fd_map: BTreeMap<Descriptor, Arc<dyn Interface>> = BTreeMap::new();
-		fd_map
-			.try_insert(STANDARD_INPUT, Arc::new(GenericStandardInput::new()))
-			.unwrap();
-		fd_map
-			.try_insert(STANDARD_OUTPUT, Arc::new(GenericStandardOutput::new()))
-			.unwrap();
-		fd_map
-			.try_insert(STANDARD_ERROR, Arc::new(GenericStandardError::new()))
-			.unwrap();
-
+	pub fn new(id: TaskId, status: TaskStatus, priority: TaskPriority, process: Rc<RefCell<Process>>) -> Task {
 		Task {
 			id,
 			priority,
+			base_priority: priority,
 			status,
 			last_stack_pointer: VirtualAddress::zero(),
 			stack: Box::new(TaskStack::new()),
-			root_page_table: get_kernel_root_page_table(),
-			fd_map,
+			process,
+			clear_child_tid: VirtualAddress::zero(),
+			fpu: FpuState::new(),
+			name: String::new(),
+			entry: None,
+			waiting: PriorityTaskQueue::new(),
+			runtime: Duration::ZERO,
+			virtual_runtime: 0,
+			slice: 0,
+			donations: BTreeMap::new(),
+			blocked_on: None,
 		}
 	}
+
+	/// Returns the base priority raised to the highest priority donated by
+	/// the waiters of held mutexes.
+	pub fn inherited_priority(&self) -> TaskPriority {
+		self.donations.values().fold(self.base_priority, |priority, donation| max(priority, *donation))
+	}
 }
 
 pub trait TaskFrame {
 	fn create_stack_frame(&mut self, func: extern "C" fn());
-}
-
-impl Drop for Task {
-	fn drop(&mut self) {
-		if self.root_page_table != get_kernel_root_page_table() {
-			debug!(
-				"deallocate page table 0x{:x} of task {}.",
-				self.root_page_table, self.id
-			);
-			deallocate(self.root_page_table, BasePageSize::SIZE);
-		}
-	}
+	fn create_fork_frame(&mut self, user_stack_pointer: VirtualAddress);
+	fn set_thread_pointer(&mut self, thread_pointer: usize);
 }
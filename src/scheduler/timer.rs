@@ -0,0 +1,121 @@
+use {
+	crate::scheduler::task::Task,
+	alloc::{
+		boxed::Box,
+		collections::{BTreeSet, BinaryHeap},
+		rc::Rc,
+	},
+	core::{
+		cell::RefCell,
+		cmp::{Ordering, Reverse},
+	},
+};
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub struct TimerId(u64);
+
+/// What happens when a timer expires
+pub enum TimerAction {
+	/// Wakes a task sleeping until the deadline
+	Wakeup(Rc<RefCell<Task>>),
+	/// Runs a callback in the timer interrupt, which must not block
+	Callback(Box<dyn FnMut()>),
+}
+
+pub struct Timer {
+	pub id: TimerId,
+	/// Tick the timer expires at
+	pub deadline: u64,
+	/// Ticks until a periodic timer expires again, zero for one-shot timers
+	pub period: u64,
+	pub action: TimerAction,
+}
+
+impl PartialEq for Timer {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl Eq for Timer {}
+
+impl PartialOrd for Timer {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Timer {
+	fn cmp(&self, other: &Self) -> Ordering {
+		(self.deadline, self.id).cmp(&(other.deadline, other.id))
+	}
+}
+
+/// Pending timers in a heap ordered by deadline. Cancelled timers stay in
+/// the heap until they expire and are skipped then.
+pub struct TimerQueue {
+	heap: BinaryHeap<Reverse<Timer>>,
+	active: BTreeSet<TimerId>,
+	next_id: u64,
+}
+
+impl TimerQueue {
+	pub fn new() -> TimerQueue {
+		TimerQueue {
+			heap: BinaryHeap::new(),
+			active: BTreeSet::new(),
+			next_id: 0,
+		}
+	}
+
+	pub fn insert(&mut self, deadline: u64, period: u64, action: TimerAction) -> TimerId {
+		let id = TimerId(self.next_id);
+		self.next_id += 1;
+
+		self.active.insert(id);
+		self.heap.push(Reverse(Timer { id, deadline, period, action }));
+
+		id
+	}
+
+	/// Queues a periodic timer again after its callback ran, unless it has
+	/// been cancelled by the callback.
+	pub fn rearm(&mut self, mut timer: Timer) {
+		if self.active.contains(&timer.id) {
+			timer.deadline = timer.deadline.saturating_add(timer.period);
+			self.heap.push(Reverse(timer));
+		}
+	}
+
+	/// Cancels the timer `id`. Returns `false` if it has already expired.
+	pub fn cancel(&mut self, id: TimerId) -> bool {
+		self.active.remove(&id)
+	}
+
+	/// Removes and returns a timer which expires at tick `now` or earlier.
+	pub fn pop_expired(&mut self, now: u64) -> Option<Timer> {
+		while let Some(Reverse(timer)) = self.heap.peek() {
+			if timer.deadline > now {
+				return None;
+			}
+
+			let Reverse(timer) = self.heap.pop().unwrap();
+
+			if timer.period == 0 {
+				if self.active.remove(&timer.id) {
+					return Some(timer);
+				}
+			} else if self.active.contains(&timer.id) {
+				return Some(timer);
+			}
+		}
+
+		None
+	}
+}
+
+impl Default for TimerQueue {
+	fn default() -> Self {
+		Self::new()
+	}
+}
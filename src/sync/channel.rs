@@ -0,0 +1,252 @@
+use {
+	crate::{
+		scheduler::{block_current_task, reschedule, save_interrupt, task::PriorityTaskQueue, wakeup_task},
+		sync::lock::WaitLockIrqSave,
+	},
+	alloc::{collections::VecDeque, sync::Arc},
+	core::fmt,
+};
+
+/// The value could not be sent because all receivers are dropped.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("SendError { .. }")
+	}
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum TrySendError<T> {
+	/// The bounded channel has no room left
+	Full(T),
+	/// All receivers are dropped
+	Disconnected(T),
+}
+
+impl<T> fmt::Debug for TrySendError<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			TrySendError::Full(_) => f.write_str("Full(..)"),
+			TrySendError::Disconnected(_) => f.write_str("Disconnected(..)"),
+		}
+	}
+}
+
+/// The channel is empty and all senders are dropped.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct RecvError;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum TryRecvError {
+	/// No value is queued
+	Empty,
+	/// No value is queued and all senders are dropped
+	Disconnected,
+}
+
+struct State<T> {
+	queue: VecDeque<T>,
+	/// Maximum number of queued values, `None` for unbounded channels
+	capacity: Option<usize>,
+	senders: usize,
+	receivers: usize,
+	waiting_senders: PriorityTaskQueue,
+	waiting_receivers: PriorityTaskQueue,
+}
+
+impl<T> State<T> {
+	fn try_send(&mut self, value: T) -> Result<(), TrySendError<T>> {
+		if self.receivers == 0 {
+			return Err(TrySendError::Disconnected(value));
+		}
+
+		if self.capacity.is_some_and(|capacity| self.queue.len() >= capacity) {
+			return Err(TrySendError::Full(value));
+		}
+
+		self.queue.push_back(value);
+		if let Some(task) = self.waiting_receivers.pop_blocked() {
+			wakeup_task(task);
+		}
+
+		Ok(())
+	}
+
+	fn try_recv(&mut self) -> Result<T, TryRecvError> {
+		match self.queue.pop_front() {
+			Some(value) => {
+				if let Some(task) = self.waiting_senders.pop_blocked() {
+					wakeup_task(task);
+				}
+
+				Ok(value)
+			}
+			None if self.senders == 0 => Err(TryRecvError::Disconnected),
+			None => Err(TryRecvError::Empty),
+		}
+	}
+}
+
+fn wakeup_all(queue: &mut PriorityTaskQueue) {
+	while let Some(task) = queue.pop_blocked() {
+		wakeup_task(task);
+	}
+}
+
+struct Channel<T> {
+	state: WaitLockIrqSave<State<T>>,
+}
+
+// the tasks in the state are only touched with interrupts disabled
+unsafe impl<T: Send> Sync for Channel<T> {}
+unsafe impl<T: Send> Send for Channel<T> {}
+
+fn new_channel<T>(capacity: Option<usize>) -> (Sender<T>, Receiver<T>) {
+	let channel = Arc::new(Channel {
+		state: WaitLockIrqSave::new(State {
+			queue: VecDeque::new(),
+			capacity,
+			senders: 1,
+			receivers: 1,
+			waiting_senders: PriorityTaskQueue::new(),
+			waiting_receivers: PriorityTaskQueue::new(),
+		}),
+	});
+
+	(Sender { channel: channel.clone() }, Receiver { channel })
+}
+
+/// Creates a channel which queues any number of values, so that sending
+/// never blocks.
+pub fn unbounded<T>() -> (Sender<T>, Receiver<T>) {
+	new_channel(None)
+}
+
+/// Creates a channel which queues up to `capacity` values. Senders block
+/// while it is full.
+pub fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+	assert!(capacity > 0, "channel without capacity");
+
+	new_channel(Some(capacity))
+}
+
+/// Sending half of a channel. Clones send into the same channel.
+pub struct Sender<T> {
+	channel: Arc<Channel<T>>,
+}
+
+impl<T> Sender<T> {
+	/// Queues `value`, blocking the current task while a bounded channel is
+	/// full. Fails once all receivers are dropped.
+	pub fn send(&self, mut value: T) -> Result<(), SendError<T>> {
+		loop {
+			let result = save_interrupt(|| {
+				let mut state = self.channel.state.lock();
+				match state.try_send(value) {
+					Err(TrySendError::Full(value)) => {
+						state.waiting_senders.push(block_current_task());
+						Err(value)
+					}
+					result => Ok(result),
+				}
+			});
+
+			match result {
+				Ok(result) => return result.map_err(|error| match error {
+					TrySendError::Full(value) | TrySendError::Disconnected(value) => SendError(value),
+				}),
+				Err(returned) => {
+					value = returned;
+					reschedule();
+				}
+			}
+		}
+	}
+
+	/// Queues `value` without blocking, so it may also be called from
+	/// interrupt handlers.
+	pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+		self.channel.state.lock().try_send(value)
+	}
+}
+
+impl<T> Clone for Sender<T> {
+	fn clone(&self) -> Self {
+		self.channel.state.lock().senders += 1;
+
+		Sender {
+			channel: self.channel.clone(),
+		}
+	}
+}
+
+impl<T> Drop for Sender<T> {
+	fn drop(&mut self) {
+		let mut state = self.channel.state.lock();
+		state.senders -= 1;
+
+		// let the receivers see the disconnect
+		if state.senders == 0 {
+			wakeup_all(&mut state.waiting_receivers);
+		}
+	}
+}
+
+/// Receiving half of a channel. Clones take values from the same channel,
+/// each value is received once.
+pub struct Receiver<T> {
+	channel: Arc<Channel<T>>,
+}
+
+impl<T> Receiver<T> {
+	/// Takes the oldest value, blocking the current task while the channel
+	/// is empty. Fails once the channel is empty and all senders are
+	/// dropped.
+	pub fn recv(&self) -> Result<T, RecvError> {
+		loop {
+			let result = save_interrupt(|| {
+				let mut state = self.channel.state.lock();
+				let result = state.try_recv();
+				if let Err(TryRecvError::Empty) = result {
+					state.waiting_receivers.push(block_current_task());
+				}
+
+				result
+			});
+
+			match result {
+				Ok(value) => return Ok(value),
+				Err(TryRecvError::Disconnected) => return Err(RecvError),
+				Err(TryRecvError::Empty) => reschedule(),
+			}
+		}
+	}
+
+	pub fn try_recv(&self) -> Result<T, TryRecvError> {
+		self.channel.state.lock().try_recv()
+	}
+}
+
+impl<T> Clone for Receiver<T> {
+	fn clone(&self) -> Self {
+		self.channel.state.lock().receivers += 1;
+
+		Receiver {
+			channel: self.channel.clone(),
+		}
+	}
+}
+
+impl<T> Drop for Receiver<T> {
+	fn drop(&mut self) {
+		let mut state = self.channel.state.lock();
+		state.receivers -= 1;
+
+		// let the senders see the disconnect
+		if state.receivers == 0 {
+			wakeup_all(&mut state.waiting_senders);
+		}
+	}
+}
@@ -0,0 +1,146 @@
+use {
+	crate::{
+		scheduler::{
+			self, block_current_task, reschedule, save_interrupt,
+			task::{PriorityTaskQueue, TaskPriority},
+			wakeup_task,
+		},
+		sync::{
+			lock::WaitLockIrqSave,
+			mutex::MutexGuard,
+			queue::WaitQueue,
+		},
+	},
+	alloc::rc::Rc,
+	core::{cell::Cell, time::Duration},
+};
+
+/// Whether `Condvar::wait_timeout` returned because the timeout elapsed
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct WaitTimeoutResult(bool);
+
+impl WaitTimeoutResult {
+	pub fn timed_out(&self) -> bool {
+		self.0
+	}
+}
+
+/// Takes `waiter`, queued with `priority`, out of `waiters` when its timeout
+/// elapses and records that in `timed_out`.
+///
+/// Returns `false` if the waiter has been notified before, which then wins
+/// over the timeout.
+fn time_out<Q: WaitQueue>(waiters: &mut Q, waiter: &Q::Waiter, priority: TaskPriority, timed_out: &Cell<bool>) -> bool {
+	let removed = waiters.remove(waiter, priority);
+	if removed {
+		timed_out.set(true);
+	}
+
+	removed
+}
+
+/// Condition variable, which blocks tasks until another task notifies it.
+/// Waiters are woken in order of priority.
+pub struct Condvar {
+	waiters: WaitLockIrqSave<PriorityTaskQueue>,
+}
+
+impl Condvar {
+	pub const fn new() -> Self {
+		Self {
+			waiters: WaitLockIrqSave::new(PriorityTaskQueue::new()),
+		}
+	}
+
+	/// Releases the mutex of `guard`, blocks the current task until it is
+	/// notified and locks the mutex again.
+	pub fn wait<'a, T>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+		// blocking and releasing must not be separated by a notification
+		let mutex = save_interrupt(|| {
+			self.waiters.lock().push(block_current_task());
+			MutexGuard::unlock(guard)
+		});
+
+		reschedule();
+
+		mutex.lock()
+	}
+
+	/// Like `wait`, but stops waiting after `timeout`, which is rounded up
+	/// to timer interrupts.
+	pub fn wait_timeout<'a, T>(&self, guard: MutexGuard<'a, T>, timeout: Duration) -> (MutexGuard<'a, T>, WaitTimeoutResult) {
+		let timed_out = Rc::new(Cell::new(false));
+
+		let (mutex, timer) = save_interrupt(|| {
+			let task = block_current_task();
+			let priority = task.borrow().priority;
+			self.waiters.lock().push(task.clone());
+
+			// the timer is cancelled before `self` goes out of scope
+			let condvar = self as *const Condvar;
+			let expired = timed_out.clone();
+			let timer = scheduler::add_timer(timeout, move || {
+				let waiters = &mut *unsafe { &*condvar }.waiters.lock();
+				if time_out(waiters, &task, priority, &expired) {
+					wakeup_task(task.clone());
+				}
+			});
+
+			(MutexGuard::unlock(guard), timer)
+		});
+
+		reschedule();
+		scheduler::cancel_timer(timer);
+
+		(mutex.lock(), WaitTimeoutResult(timed_out.get()))
+	}
+
+	/// Wakes the waiting task of highest priority.
+	pub fn notify_one(&self) {
+		if let Some(task) = self.waiters.lock().pop_blocked() {
+			wakeup_task(task);
+		}
+	}
+
+	/// Wakes all waiting tasks.
+	pub fn notify_all(&self) {
+		let mut waiters = self.waiters.lock();
+		while let Some(task) = waiters.pop_blocked() {
+			wakeup_task(task);
+		}
+	}
+}
+
+impl Default for Condvar {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+// the tasks in the queue are only touched with interrupts disabled
+unsafe impl Sync for Condvar {}
+unsafe impl Send for Condvar {}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn condvar_timeout() {
+	use {crate::scheduler::task::NORMAL_PRIORITY, alloc::collections::VecDeque};
+
+	let mut waiters = VecDeque::new();
+	let first = Cell::new(false);
+	let second = Cell::new(false);
+
+	waiters.push_back(1);
+	waiters.push_back(2);
+
+	// the first waiter is notified before its timeout elapses
+	assert_eq!(WaitQueue::pop(&mut waiters), Some(1));
+	assert!(!time_out(&mut waiters, &1, NORMAL_PRIORITY, &first));
+	assert!(!first.get());
+
+	// the second one times out and is not notified anymore
+	assert!(time_out(&mut waiters, &2, NORMAL_PRIORITY, &second));
+	assert!(second.get());
+	assert_eq!(WaitQueue::pop(&mut waiters), None);
+	assert!(!time_out(&mut waiters, &2, NORMAL_PRIORITY, &second));
+}
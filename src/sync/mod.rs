@@ -1,2 +1,7 @@
+pub mod channel;
+pub mod condvar;
 pub mod mutex;
 pub mod lock;
+mod queue;
+pub mod rwlock;
+pub mod semaphore;
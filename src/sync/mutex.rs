@@ -1,35 +1,184 @@
 use {
 	crate::{
 		scheduler::{
-			block_current_task, reschedule, wakeup_task,
-			task::{PriorityTaskQueue},
+			block_current_task, change_priority, get_current_task, reschedule, save_interrupt, wakeup_task,
+			task::{PriorityTaskQueue, Task, TaskPriority},
 		},
 		sync::lock::WaitLockIrqSave,
 	},
+	alloc::rc::Rc,
 	core::{
-		cell::UnsafeCell,
+		cell::{RefCell, UnsafeCell},
+		cmp::max,
 		marker::{PhantomData, Send, Sync},
+		mem,
 		ops::{Deref, DerefMut},
 	},
 };
 
+struct State {
+	owner: Option<Rc<RefCell<Task>>>,
+	waiters: PriorityTaskQueue,
+}
+
+/// Lock without data with priority inheritance
+///
+/// On release the lock is handed over to the waiter of highest priority.
+/// While held, the owner inherits the priority of its waiters, also along
+/// chains of owners waiting for other mutexes.
+pub struct RawMutex {
+	state: WaitLockIrqSave<State>,
+}
+
+impl RawMutex {
+	pub const fn new() -> Self {
+		Self {
+			state: WaitLockIrqSave::new(State {
+				owner: None,
+				waiters: PriorityTaskQueue::new(),
+			}),
+		}
+	}
+
+	fn key(&self) -> usize {
+		self as *const RawMutex as usize
+	}
+
+	pub fn lock(&self) {
+		let blocked = save_interrupt(|| {
+			let priority = {
+				let mut state = self.state.lock();
+				if state.owner.is_none() {
+					state.owner = Some(get_current_task());
+					return false;
+				}
+
+				let task = block_current_task();
+				let priority = {
+					let mut borrowed = task.borrow_mut();
+					borrowed.blocked_on = Some(self as *const RawMutex);
+					borrowed.priority
+				};
+				state.waiters.push(task);
+				priority
+			};
+
+			donate(self, priority);
+			true
+		});
+
+		// woken up by `unlock` after the mutex has been handed over
+		if blocked {
+			reschedule();
+		}
+	}
+
+	pub fn try_lock(&self) -> bool {
+		let mut state = self.state.lock();
+		if state.owner.is_none() {
+			state.owner = Some(get_current_task());
+			true
+		} else {
+			false
+		}
+	}
+
+	pub fn unlock(&self) {
+		save_interrupt(|| {
+			let next = {
+				let mut state = self.state.lock();
+				let next = state.waiters.pop_blocked();
+
+				if let Some(next) = &next {
+					let mut borrowed = next.borrow_mut();
+					borrowed.blocked_on = None;
+					if let Some(priority) = state.waiters.highest_priority() {
+						borrowed.donations.insert(self.key(), priority);
+					}
+				}
+
+				state.owner = next.clone();
+				next
+			};
+
+			let current = get_current_task();
+			current.borrow_mut().donations.remove(&self.key());
+			let priority = current.borrow().inherited_priority();
+			change_priority(&current, priority);
+
+			if let Some(next) = next {
+				let priority = next.borrow().inherited_priority();
+				change_priority(&next, priority);
+				wakeup_task(next);
+			}
+		});
+	}
+}
+
+impl Default for RawMutex {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+// the tasks in the state are only touched with interrupts disabled
+unsafe impl Sync for RawMutex {}
+unsafe impl Send for RawMutex {}
+
+/// Raises the owner of `mutex` to `priority` and follows the chain of
+/// mutexes the owners wait for. Interrupts have to be disabled.
+fn donate(mut mutex: *const RawMutex, priority: TaskPriority) {
+	loop {
+		// the waiters keep the mutexes of the chain borrowed
+		let mutex_ref = unsafe { &*mutex };
+		let owner = match &mutex_ref.state.lock().owner {
+			Some(owner) => owner.clone(),
+			None => return,
+		};
+
+		let (old_priority, new_priority, blocked_on) = {
+			let mut borrowed = owner.borrow_mut();
+			let donation = borrowed.donations.entry(mutex_ref.key()).or_insert(priority);
+			*donation = max(*donation, priority);
+			(borrowed.priority, borrowed.inherited_priority(), borrowed.blocked_on)
+		};
+
+		if new_priority <= old_priority {
+			return;
+		}
+
+		match blocked_on {
+			Some(next) => {
+				// queue the owner again with its new priority
+				let mut state = unsafe { &*next }.state.lock();
+				state.waiters.remove(&owner, old_priority);
+				owner.borrow_mut().priority = new_priority;
+				state.waiters.push(owner);
+				mutex = next;
+			}
+			None => {
+				change_priority(&owner, new_priority);
+				return;
+			}
+		}
+	}
+}
+
 pub struct Mutex<T> {
-	is_locked: WaitLockIrqSave<bool>,
-	wait_queue: WaitLockIrqSave<PriorityTaskQueue>,
+	raw: RawMutex,
 	data: UnsafeCell<T>,
 }
 
 impl<T> Mutex<T> {
 	pub const fn new(data: T) -> Self {
 		Self {
-			is_locked: WaitLockIrqSave::new(false),
-			wait_queue: WaitLockIrqSave::new(PriorityTaskQueue::new()),
+			raw: RawMutex::new(),
 			data: UnsafeCell::new(data),
 		}
 	}
 
 	pub fn lock(&self) -> MutexGuard<'_, T> {
-		self.acquire_lock();
+		self.raw.lock();
 		MutexGuard {
 			mutex: self,
 			_phantom: PhantomData,
@@ -37,7 +186,7 @@ impl<T> Mutex<T> {
 	}
 
 	pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
-		if self.try_acquire_lock() {
+		if self.raw.try_lock() {
 			Some(MutexGuard {
 				mutex: self,
 				_phantom: PhantomData,
@@ -50,42 +199,6 @@ impl<T> Mutex<T> {
 	pub fn into_inner(self) -> T {
 		self.data.into_inner()
 	}
-
-	fn acquire_lock(&self) {
-		loop {
-			{
-				let mut locked = self.is_locked.lock();
-				if !*locked {
-					*locked = true;
-					return;
-				}
-
-				let task = block_current_task();
-				self.wait_queue.lock().push(task);
-			}
-
-			reschedule();
-		}
-	}
-
-	fn try_acquire_lock(&self) -> bool {
-		let mut locked = self.is_locked.lock();
-		if !*locked {
-			*locked = true;
-			true
-		} else {
-			false
-		}
-	}
-
-	fn release_lock(&self) {
-		let mut locked = self.is_locked.lock();
-		*locked = false;
-
-		if let Some(task) = self.wait_queue.lock().pop() {
-			wakeup_task(task);
-		}
-	}
 }
 
 impl<T: Default> Default for Mutex<T> {
@@ -102,6 +215,17 @@ pub struct MutexGuard<'a, T> {
 	_phantom: PhantomData<&'a mut T>,
 }
 
+impl<'a, T> MutexGuard<'a, T> {
+	/// Releases the lock and returns the mutex to lock it again.
+	pub(super) fn unlock(guard: Self) -> &'a Mutex<T> {
+		let mutex = guard.mutex;
+		mem::forget(guard);
+		mutex.raw.unlock();
+
+		mutex
+	}
+}
+
 impl<'a, T> Deref for MutexGuard<'a, T> {
 	type Target = T;
 
@@ -118,6 +242,6 @@ impl<'a, T> DerefMut for MutexGuard<'a, T> {
 
 impl<'a, T> Drop for MutexGuard<'a, T> {
 	fn drop(&mut self) {
-		self.mutex.release_lock();
+		self.mutex.raw.unlock();
 	}
 }
\ No newline at end of file
@@ -0,0 +1,65 @@
+use {
+	crate::scheduler::task::{PriorityTaskQueue, Task, TaskPriority},
+	alloc::{collections::VecDeque, rc::Rc},
+	core::cell::RefCell,
+};
+
+/// Queue of blocked tasks. The state of the sleeping locks is generic over
+/// it, so that it can be tested without a scheduler.
+pub(crate) trait WaitQueue {
+	type Waiter;
+
+	fn push(&mut self, waiter: Self::Waiter);
+
+	/// Removes the next waiter, skipping tasks which are no longer blocked.
+	fn pop(&mut self) -> Option<Self::Waiter>;
+
+	fn is_empty(&self) -> bool;
+
+	/// Removes `waiter`, which has been queued with `priority`.
+	fn remove(&mut self, waiter: &Self::Waiter, priority: TaskPriority) -> bool;
+}
+
+impl WaitQueue for PriorityTaskQueue {
+	type Waiter = Rc<RefCell<Task>>;
+
+	fn push(&mut self, waiter: Self::Waiter) {
+		PriorityTaskQueue::push(self, waiter)
+	}
+
+	fn pop(&mut self) -> Option<Self::Waiter> {
+		PriorityTaskQueue::pop_blocked(self)
+	}
+
+	fn is_empty(&self) -> bool {
+		PriorityTaskQueue::is_empty(self)
+	}
+
+	fn remove(&mut self, waiter: &Self::Waiter, priority: TaskPriority) -> bool {
+		PriorityTaskQueue::remove(self, waiter, priority)
+	}
+}
+
+/// First in, first out queue ignoring the priority
+impl<T: PartialEq> WaitQueue for VecDeque<T> {
+	type Waiter = T;
+
+	fn push(&mut self, waiter: T) {
+		self.push_back(waiter)
+	}
+
+	fn pop(&mut self) -> Option<T> {
+		self.pop_front()
+	}
+
+	fn is_empty(&self) -> bool {
+		VecDeque::is_empty(self)
+	}
+
+	fn remove(&mut self, waiter: &T, _priority: TaskPriority) -> bool {
+		match self.iter().position(|queued| queued == waiter) {
+			Some(position) => VecDeque::remove(self, position).is_some(),
+			None => false,
+		}
+	}
+}
@@ -0,0 +1,317 @@
+use {
+	crate::{
+		scheduler::{block_current_task, reschedule, save_interrupt, task::PriorityTaskQueue, wakeup_task},
+		sync::{lock::WaitLockIrqSave, queue::WaitQueue},
+	},
+	core::{
+		cell::UnsafeCell,
+		marker::{PhantomData, Send, Sync},
+		ops::{Deref, DerefMut},
+	},
+};
+
+struct State<Q> {
+	readers: usize,
+	writer: bool,
+	waiting_readers: Q,
+	waiting_writers: Q,
+}
+
+impl<Q: WaitQueue> State<Q> {
+	/// Admits a reader. Otherwise the task returned by `waiter` is queued
+	/// and `false` is returned.
+	fn read(&mut self, waiter: impl FnOnce() -> Q::Waiter) -> bool {
+		if self.try_read() {
+			return true;
+		}
+
+		self.waiting_readers.push(waiter());
+		false
+	}
+
+	fn try_read(&mut self) -> bool {
+		if !self.writer && self.waiting_writers.is_empty() {
+			self.readers += 1;
+			true
+		} else {
+			false
+		}
+	}
+
+	/// Admits a writer. Otherwise the task returned by `waiter` is queued
+	/// and `false` is returned.
+	fn write(&mut self, waiter: impl FnOnce() -> Q::Waiter) -> bool {
+		if self.try_write() {
+			return true;
+		}
+
+		self.waiting_writers.push(waiter());
+		false
+	}
+
+	fn try_write(&mut self) -> bool {
+		if !self.writer && self.readers == 0 {
+			self.writer = true;
+			true
+		} else {
+			false
+		}
+	}
+
+	/// Hands the lock over to all waiting readers. Returns `false` if
+	/// there are none.
+	fn admit_readers(&mut self, wake: &mut impl FnMut(Q::Waiter)) -> bool {
+		let readers = self.readers;
+
+		while let Some(reader) = self.waiting_readers.pop() {
+			self.readers += 1;
+			wake(reader);
+		}
+
+		self.readers > readers
+	}
+
+	/// Hands the lock over to the next waiting writer. Returns `false` if
+	/// there is none.
+	fn admit_writer(&mut self, wake: &mut impl FnMut(Q::Waiter)) -> bool {
+		match self.waiting_writers.pop() {
+			Some(writer) => {
+				self.writer = true;
+				wake(writer);
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Releases a read lock and passes the tasks which now hold it to
+	/// `wake`. The last reader hands the lock over to a writer first.
+	///
+	/// Readers may wait although no writer is left, if the writers have
+	/// been terminated while they were waiting.
+	fn read_unlock(&mut self, mut wake: impl FnMut(Q::Waiter)) {
+		self.readers -= 1;
+
+		if self.readers == 0 && !self.admit_writer(&mut wake) {
+			self.admit_readers(&mut wake);
+		}
+	}
+
+	/// Releases the write lock and passes the tasks which now hold it to
+	/// `wake`. Waiting readers are admitted before the next writer.
+	fn write_unlock(&mut self, mut wake: impl FnMut(Q::Waiter)) {
+		self.writer = false;
+
+		if !self.admit_readers(&mut wake) {
+			self.admit_writer(&mut wake);
+		}
+	}
+}
+
+/// Reader-writer lock whose waiters sleep instead of spinning
+///
+/// New readers wait while a writer waits, so that writers do not starve.
+/// Releasing a write lock admits all waiting readers, otherwise the next
+/// writer. The lock is handed over, a woken task already holds it.
+pub struct RwLock<T> {
+	state: WaitLockIrqSave<State<PriorityTaskQueue>>,
+	data: UnsafeCell<T>,
+}
+
+impl<T> RwLock<T> {
+	pub const fn new(data: T) -> Self {
+		Self {
+			state: WaitLockIrqSave::new(State {
+				readers: 0,
+				writer: false,
+				waiting_readers: PriorityTaskQueue::new(),
+				waiting_writers: PriorityTaskQueue::new(),
+			}),
+			data: UnsafeCell::new(data),
+		}
+	}
+
+	pub fn read(&self) -> RwLockReadGuard<'_, T> {
+		let admitted = save_interrupt(|| self.state.lock().read(block_current_task));
+
+		if !admitted {
+			reschedule();
+		}
+
+		RwLockReadGuard {
+			lock: self,
+			_phantom: PhantomData,
+		}
+	}
+
+	pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+		if self.state.lock().try_read() {
+			Some(RwLockReadGuard {
+				lock: self,
+				_phantom: PhantomData,
+			})
+		} else {
+			None
+		}
+	}
+
+	pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+		let admitted = save_interrupt(|| self.state.lock().write(block_current_task));
+
+		if !admitted {
+			reschedule();
+		}
+
+		RwLockWriteGuard {
+			lock: self,
+			_phantom: PhantomData,
+		}
+	}
+
+	pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+		if self.state.lock().try_write() {
+			Some(RwLockWriteGuard {
+				lock: self,
+				_phantom: PhantomData,
+			})
+		} else {
+			None
+		}
+	}
+
+	pub fn into_inner(self) -> T {
+		self.data.into_inner()
+	}
+
+	fn read_unlock(&self) {
+		self.state.lock().read_unlock(wakeup_task);
+	}
+
+	fn write_unlock(&self) {
+		self.state.lock().write_unlock(wakeup_task);
+	}
+}
+
+impl<T: Default> Default for RwLock<T> {
+	fn default() -> Self {
+		Self::new(T::default())
+	}
+}
+
+unsafe impl<T: Send + Sync> Sync for RwLock<T> {}
+unsafe impl<T: Send> Send for RwLock<T> {}
+
+pub struct RwLockReadGuard<'a, T> {
+	lock: &'a RwLock<T>,
+	_phantom: PhantomData<&'a T>,
+}
+
+impl<'a, T> Deref for RwLockReadGuard<'a, T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		unsafe { &*self.lock.data.get() }
+	}
+}
+
+impl<'a, T> Drop for RwLockReadGuard<'a, T> {
+	fn drop(&mut self) {
+		self.lock.read_unlock();
+	}
+}
+
+pub struct RwLockWriteGuard<'a, T> {
+	lock: &'a RwLock<T>,
+	_phantom: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Deref for RwLockWriteGuard<'a, T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		unsafe { &*self.lock.data.get() }
+	}
+}
+
+impl<'a, T> DerefMut for RwLockWriteGuard<'a, T> {
+	fn deref_mut(&mut self) -> &mut T {
+		unsafe { &mut *self.lock.data.get() }
+	}
+}
+
+impl<'a, T> Drop for RwLockWriteGuard<'a, T> {
+	fn drop(&mut self) {
+		self.lock.write_unlock();
+	}
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn rwlock_admission() {
+	use alloc::{collections::VecDeque, vec::Vec};
+
+	let mut state = State {
+		readers: 0,
+		writer: false,
+		waiting_readers: VecDeque::new(),
+		waiting_writers: VecDeque::new(),
+	};
+	let mut woken = Vec::new();
+
+	assert!(state.read(|| 1));
+	assert!(state.try_read());
+	assert!(!state.try_write());
+
+	// a waiting writer keeps new readers out
+	assert!(!state.write(|| 3));
+	assert!(!state.try_read());
+	assert!(!state.read(|| 4));
+	assert!(!state.read(|| 5));
+
+	state.read_unlock(|task| woken.push(task));
+	assert!(woken.is_empty());
+	state.read_unlock(|task| woken.push(task));
+	assert_eq!(woken, [3]);
+	assert!(state.writer);
+	assert_eq!(state.readers, 0);
+
+	// releasing the writer admits all waiting readers
+	assert!(!state.write(|| 6));
+	woken.clear();
+	state.write_unlock(|task| woken.push(task));
+	assert_eq!(woken, [4, 5]);
+	assert!(!state.writer);
+	assert_eq!(state.readers, 2);
+
+	// and the last reader the next writer
+	woken.clear();
+	state.read_unlock(|task| woken.push(task));
+	state.read_unlock(|task| woken.push(task));
+	assert_eq!(woken, [6]);
+
+	woken.clear();
+	state.write_unlock(|task| woken.push(task));
+	assert!(woken.is_empty());
+	assert!(!state.writer);
+	assert!(state.try_write());
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn rwlock_without_writers() {
+	use alloc::{collections::VecDeque, vec::Vec};
+
+	// the writer the readers waited for has been terminated
+	let mut state = State {
+		readers: 1,
+		writer: false,
+		waiting_readers: VecDeque::from([7, 8]),
+		waiting_writers: VecDeque::new(),
+	};
+	let mut woken = Vec::new();
+
+	state.read_unlock(|task| woken.push(task));
+	assert_eq!(woken, [7, 8]);
+	assert_eq!(state.readers, 2);
+	assert!(!state.writer);
+}
@@ -0,0 +1,116 @@
+use {
+	crate::{
+		scheduler::{block_current_task, reschedule, save_interrupt, task::PriorityTaskQueue, wakeup_task},
+		sync::{lock::WaitLockIrqSave, queue::WaitQueue},
+	},
+};
+
+struct State<Q> {
+	count: usize,
+	waiters: Q,
+}
+
+impl<Q: WaitQueue> State<Q> {
+	/// Takes a permit. Without one, the task returned by `waiter` is queued
+	/// and `false` is returned.
+	fn acquire(&mut self, waiter: impl FnOnce() -> Q::Waiter) -> bool {
+		if self.try_acquire() {
+			return true;
+		}
+
+		self.waiters.push(waiter());
+		false
+	}
+
+	fn try_acquire(&mut self) -> bool {
+		if self.count > 0 {
+			self.count -= 1;
+			true
+		} else {
+			false
+		}
+	}
+
+	/// Returns a permit. A waiter gets it handed over and is returned to be
+	/// woken up.
+	fn release(&mut self) -> Option<Q::Waiter> {
+		let waiter = self.waiters.pop();
+		if waiter.is_none() {
+			self.count += 1;
+		}
+
+		waiter
+	}
+}
+
+/// Counting semaphore. A released permit is handed over to the waiter of
+/// highest priority.
+pub struct Semaphore {
+	state: WaitLockIrqSave<State<PriorityTaskQueue>>,
+}
+
+impl Semaphore {
+	pub const fn new(count: usize) -> Self {
+		Self {
+			state: WaitLockIrqSave::new(State {
+				count,
+				waiters: PriorityTaskQueue::new(),
+			}),
+		}
+	}
+
+	/// Takes a permit, blocking the current task until one is available.
+	pub fn acquire(&self) {
+		let acquired = save_interrupt(|| self.state.lock().acquire(block_current_task));
+
+		// woken up by `release` with the permit handed over
+		if !acquired {
+			reschedule();
+		}
+	}
+
+	pub fn try_acquire(&self) -> bool {
+		self.state.lock().try_acquire()
+	}
+
+	/// Returns a permit.
+	pub fn release(&self) {
+		let mut state = self.state.lock();
+		if let Some(task) = state.release() {
+			wakeup_task(task);
+		}
+	}
+
+	/// Returns the number of available permits.
+	pub fn available(&self) -> usize {
+		self.state.lock().count
+	}
+}
+
+// the tasks in the queue are only touched with interrupts disabled
+unsafe impl Sync for Semaphore {}
+unsafe impl Send for Semaphore {}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn semaphore_permits() {
+	use alloc::collections::VecDeque;
+
+	let mut state = State { count: 2, waiters: VecDeque::new() };
+
+	assert!(state.acquire(|| 1));
+	assert!(state.try_acquire());
+	assert!(!state.try_acquire());
+	assert!(!state.acquire(|| 3));
+	assert!(!state.acquire(|| 4));
+	assert_eq!(state.count, 0);
+
+	// released permits go to the waiters first
+	assert_eq!(state.release(), Some(3));
+	assert_eq!(state.release(), Some(4));
+	assert_eq!(state.count, 0);
+
+	assert_eq!(state.release(), None);
+	assert_eq!(state.release(), None);
+	assert_eq!(state.count, 2);
+}
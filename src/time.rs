@@ -0,0 +1,87 @@
+use {
+	crate::{
+		arch::kernel::devices::{rtc, timer},
+		consts::TIMER_FREQ,
+	},
+	core::{
+		sync::atomic::{AtomicU64, Ordering},
+		time::Duration,
+	},
+};
+
+/// Time between two timer interrupts
+pub const TICK: Duration = Duration::from_nanos(1_000_000_000 / TIMER_FREQ as u64);
+
+/// Timer interrupts since boot
+static TICKS: AtomicU64 = AtomicU64::new(0);
+
+/// Cycles of the time stamp counter per second, zero if unknown
+static TSC_FREQUENCY: AtomicU64 = AtomicU64::new(0);
+
+/// Time stamp counter when the clocks were started
+static BOOT_TIMESTAMP: AtomicU64 = AtomicU64::new(0);
+
+/// Seconds since the Unix epoch when the clocks were started
+static BOOT_REALTIME: AtomicU64 = AtomicU64::new(0);
+
+/// Calibrates the time stamp counter and seeds the realtime clock from the
+/// CMOS clock.
+pub fn initialize() {
+	let frequency = timer::calibrate_tsc();
+	let realtime = rtc::read_time();
+
+	BOOT_TIMESTAMP.store(timer::get_timestamp(), Ordering::SeqCst);
+	BOOT_REALTIME.store(realtime, Ordering::SeqCst);
+	TSC_FREQUENCY.store(frequency, Ordering::SeqCst);
+
+	info!("TSC runs at {} MHz, realtime clock starts at {} s.", frequency / 1_000_000, realtime);
+}
+
+/// Counts a timer interrupt and returns the ticks since boot.
+pub fn tick() -> u64 {
+	TICKS.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+/// Returns the timer interrupts since boot.
+pub fn ticks() -> u64 {
+	TICKS.load(Ordering::SeqCst)
+}
+
+/// Returns the time since boot, measured by the time stamp counter or, if
+/// it has not been calibrated, by the timer interrupts.
+pub fn monotonic() -> Duration {
+	let frequency = TSC_FREQUENCY.load(Ordering::SeqCst);
+
+	if frequency == 0 {
+		return Duration::from_nanos(TICK.as_nanos() as u64 * ticks());
+	}
+
+	let cycles = timer::get_timestamp().saturating_sub(BOOT_TIMESTAMP.load(Ordering::SeqCst));
+	let nanos = u128::from(cycles) * 1_000_000_000 / u128::from(frequency);
+
+	Duration::new((nanos / 1_000_000_000) as u64, (nanos % 1_000_000_000) as u32)
+}
+
+/// Returns the time since the Unix epoch.
+pub fn realtime() -> Duration {
+	Duration::from_secs(BOOT_REALTIME.load(Ordering::SeqCst)) + monotonic()
+}
+
+/// Returns the number of ticks which last at least `duration`, saturated
+/// at `u64::MAX`.
+pub fn duration_to_ticks(duration: Duration) -> u64 {
+	let tick = TICK.as_nanos();
+
+	u64::try_from(duration.as_nanos().div_ceil(tick)).unwrap_or(u64::MAX)
+}
+
+#[cfg(not(target_os = "none"))]
+#[test]
+fn ticks_of_durations() {
+	assert_eq!(duration_to_ticks(Duration::ZERO), 0);
+	assert_eq!(duration_to_ticks(Duration::from_nanos(1)), 1);
+	assert_eq!(duration_to_ticks(TICK), 1);
+	assert_eq!(duration_to_ticks(TICK + Duration::from_nanos(1)), 2);
+	assert_eq!(duration_to_ticks(Duration::from_secs(1)), TIMER_FREQ as u64);
+	assert_eq!(duration_to_ticks(Duration::MAX), u64::MAX);
+}